@@ -0,0 +1,29 @@
+use axum::response::sse::Event;
+use futures_util::stream::{self, Stream};
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::core::DownloadEvent;
+
+/// Stream of download events as Server-Sent Events
+///
+/// Each event is named after its type (`queued`, `progress`, ...) and carries
+/// the event as JSON. A client that falls too far behind gets a `lagged`
+/// event and should re-fetch `GET /api/downloads`.
+pub fn download_events(
+    events: broadcast::Receiver<DownloadEvent>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(events, |mut events| async move {
+        let event = match events.recv().await {
+            Ok(event) => sse_event(&event),
+            Err(RecvError::Lagged(missed)) => Event::default().event("lagged").data(missed.to_string()),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), events))
+    })
+}
+
+fn sse_event(event: &DownloadEvent) -> Event {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    Event::default().event(event.name()).data(data)
+}
@@ -0,0 +1,294 @@
+// Local HTTP/JSON control API
+// Lets scripts and other tools drive a running ytdl-mini; only listens on 127.0.0.1
+
+mod events;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{Ipv4Addr, SocketAddr};
+use uuid::Uuid;
+
+use crate::core::{AppState, DownloadItem, GroupSummary, Settings, YtdlError};
+
+/// Start the API in the background if it is enabled in settings
+///
+/// A token is generated and saved the first time the API starts.
+pub async fn spawn(state: AppState) -> Result<Option<SocketAddr>, YtdlError> {
+    let mut settings = state.get_settings().await;
+    if !settings.api.enabled {
+        return Ok(None);
+    }
+
+    if settings.api.token.is_empty() {
+        settings.api.token = Uuid::new_v4().simple().to_string();
+        settings.save()?;
+        state.update_settings(settings.clone()).await;
+    }
+
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, settings.api.port)).await?;
+    let address = listener.local_addr()?;
+    let app = router(state, settings.api.token);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            log::error!("Control API stopped: {}", e);
+        }
+    });
+
+    log::info!("Control API listening on http://{}", address);
+    Ok(Some(address))
+}
+
+/// Routes of the API, all behind the token check
+fn router(state: AppState, token: String) -> Router {
+    Router::new()
+        .route("/api/downloads", get(list_downloads).post(add_downloads))
+        .route("/api/downloads/clear", post(clear_completed))
+        .route("/api/downloads/{id}", get(get_download).delete(remove_download))
+        .route("/api/downloads/{id}/cancel", post(cancel_download))
+        .route("/api/downloads/{id}/retry", post(retry_download))
+        .route("/api/groups", get(list_groups))
+        .route("/api/settings", get(get_settings).patch(update_settings))
+        .route("/api/events", get(download_events))
+        .layer(middleware::from_fn_with_state(token, require_token))
+        .with_state(state)
+}
+
+/// Reject requests without the API token
+///
+/// The token is sent as `Authorization: Bearer <token>`, or as a `token`
+/// query parameter for clients such as `EventSource` that can't set headers.
+async fn require_token(
+    State(token): State<String>,
+    Query(query): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| query.get("token").map(|token| token.as_str()));
+
+    match provided {
+        Some(provided) if constant_time_eq(provided.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid API token").into_response(),
+    }
+}
+
+/// Compare secrets without leaking where they differ through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// An error response with a JSON body: `{"error": "..."}`
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+impl From<YtdlError> for ApiError {
+    fn from(error: YtdlError) -> Self {
+        let status = match &error {
+            YtdlError::InvalidUrl(_) | YtdlError::InvalidSetting(_) => StatusCode::BAD_REQUEST,
+            YtdlError::NotFound(_) => StatusCode::NOT_FOUND,
+            YtdlError::InvalidState(_) => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Body of `POST /api/downloads`: one URL or several
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum AddRequest {
+    One { url: String },
+    Many { urls: Vec<String> },
+}
+
+async fn list_downloads(State(state): State<AppState>) -> Json<Vec<DownloadItem>> {
+    Json(state.get_downloads().await)
+}
+
+/// Queue URLs; playlists, channels and mixes are expanded into a group
+async fn add_downloads(
+    State(state): State<AppState>,
+    Json(request): Json<AddRequest>,
+) -> ApiResult<impl IntoResponse> {
+    let urls = match request {
+        AddRequest::One { url } => vec![url],
+        AddRequest::Many { urls } => urls,
+    };
+
+    let mut ids = Vec::new();
+    for url in urls {
+        ids.push(state.add_download(url).await?);
+    }
+    Ok((StatusCode::CREATED, Json(json!({ "ids": ids }))))
+}
+
+async fn get_download(State(state): State<AppState>, Path(id): Path<Uuid>) -> ApiResult<Json<DownloadItem>> {
+    state
+        .get_downloads()
+        .await
+        .into_iter()
+        .find(|download| download.id == id)
+        .map(Json)
+        .ok_or_else(|| YtdlError::NotFound(id).into())
+}
+
+async fn cancel_download(State(state): State<AppState>, Path(id): Path<Uuid>) -> ApiResult<StatusCode> {
+    state.download_manager.write().await.cancel_download(id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn retry_download(State(state): State<AppState>, Path(id): Path<Uuid>) -> ApiResult<StatusCode> {
+    state.download_manager.write().await.retry_download(id)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_download(State(state): State<AppState>, Path(id): Path<Uuid>) -> ApiResult<StatusCode> {
+    state
+        .download_manager
+        .write()
+        .await
+        .remove_download(id)
+        .ok_or(YtdlError::NotFound(id))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn clear_completed(State(state): State<AppState>) -> StatusCode {
+    state.download_manager.write().await.clear_completed();
+    StatusCode::NO_CONTENT
+}
+
+/// Playlist groups with the aggregate progress of their items
+async fn list_groups(State(state): State<AppState>) -> Json<Vec<GroupSummary>> {
+    Json(state.get_groups().await)
+}
+
+async fn get_settings(State(state): State<AppState>) -> Json<Settings> {
+    Json(state.get_settings().await)
+}
+
+/// Change settings by name, e.g. `{"max_concurrent_downloads": 2, "subtitles.enabled": true}`
+///
+/// Nothing is changed unless every value is valid.
+async fn update_settings(
+    State(state): State<AppState>,
+    Json(changes): Json<BTreeMap<String, serde_json::Value>>,
+) -> ApiResult<Json<Settings>> {
+    let mut settings = state.get_settings().await;
+    for (key, value) in changes {
+        let text = match value {
+            serde_json::Value::String(text) => text,
+            value => value.to_string(),
+        };
+        settings.set_value(&key, &text)?;
+    }
+
+    settings.save()?;
+    state.update_settings(settings.clone()).await;
+    Ok(Json(settings))
+}
+
+/// Server-Sent Events stream of download changes
+async fn download_events(State(state): State<AppState>) -> impl IntoResponse {
+    Sse::new(events::download_events(state.subscribe().await)).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serve the API on a free port and return its base URL
+    async fn serve(token: &str) -> String {
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = router(AppState::in_memory(), token.to_string());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base_url
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let base_url = serve("secret").await;
+        let client = reqwest::Client::new();
+
+        let response = client.get(format!("{}/api/downloads", base_url)).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(format!("{}/api/downloads", base_url))
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(format!("{}/api/downloads?token=secret", base_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_errors_map_to_status_codes() {
+        let base_url = serve("secret").await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(format!("{}/api/downloads/{}", base_url, Uuid::new_v4()))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        let response = client
+            .patch(format!("{}/api/settings", base_url))
+            .bearer_auth("secret")
+            .json(&json!({ "default_resolution": "huge" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("resolution"));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"tok"));
+    }
+}
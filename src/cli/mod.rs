@@ -0,0 +1,503 @@
+// Headless command-line interface
+// Drives the same AppState and DownloadManager as the GUI, without opening a window
+
+mod progress;
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::core::{
+    AppState, AudioFormat, DownloadItem, DownloadMode, DownloadStatus, GroupSummary,
+    PlaylistSelection, Settings, YtDlp, YtdlError,
+};
+use crate::utils::{format_bytes, format_duration};
+use progress::ProgressDisplay;
+
+/// Everything worked
+pub const EXIT_OK: i32 = 0;
+/// A command or at least one download failed
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid arguments, URLs or settings
+pub const EXIT_USAGE: i32 = 2;
+/// yt-dlp is missing, too old or could not be installed
+pub const EXIT_YTDLP: i32 = 3;
+/// Stopped with Ctrl-C; running downloads were paused
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    success
+  1    a command or download failed
+  2    invalid arguments, URLs or settings
+  3    yt-dlp is missing, too old or could not be installed
+  130  interrupted; running downloads were paused";
+
+/// ytdl-mini command line; without a subcommand the desktop app opens
+#[derive(Debug, Parser)]
+#[command(name = "ytdl-mini", version, about, after_help = EXIT_CODES_HELP)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Queue downloads without starting them
+    Add {
+        /// Video, playlist, channel or mix URLs
+        #[arg(required = true)]
+        urls: Vec<String>,
+        #[command(flatten)]
+        options: QueueOptions,
+    },
+    /// List queued and finished downloads
+    List {
+        /// Print the queue as JSON
+        #[arg(long)]
+        json: bool,
+        /// List playlist groups and their progress instead of single downloads
+        #[arg(long)]
+        groups: bool,
+    },
+    /// Show a video's metadata
+    Info {
+        url: String,
+        /// Print yt-dlp's full metadata as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the formats available for a video
+    Formats { url: String },
+    /// Download the given URLs, or everything queued if none are given
+    Download {
+        /// Video, playlist, channel or mix URLs
+        urls: Vec<String>,
+        #[command(flatten)]
+        options: QueueOptions,
+    },
+    /// Show or change settings
+    Settings {
+        #[command(subcommand)]
+        action: SettingsAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SettingsAction {
+    /// Print one setting, or all of them
+    Get { key: Option<String> },
+    /// Change a setting, e.g. `settings set subtitles.enabled true`
+    Set { key: String, value: String },
+}
+
+/// How to queue new downloads
+#[derive(Debug, Args)]
+pub struct QueueOptions {
+    /// Extract audio instead of downloading video
+    #[arg(long)]
+    audio: bool,
+    /// Audio format to extract to; implies --audio
+    #[arg(long, value_parser = parse_audio_format)]
+    audio_format: Option<AudioFormat>,
+    /// Maximum video resolution, e.g. 1280x720
+    #[arg(long, conflicts_with_all = ["audio", "audio_format"])]
+    resolution: Option<String>,
+    /// Playlist entries to queue, e.g. "1-5,8"
+    #[arg(long)]
+    items: Option<String>,
+}
+
+fn parse_audio_format(value: &str) -> Result<AudioFormat, String> {
+    AudioFormat::ALL
+        .into_iter()
+        .find(|format| format.as_str().eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            let names: Vec<_> = AudioFormat::ALL.iter().map(|f| f.as_str()).collect();
+            format!("expected one of {}", names.join(", "))
+        })
+}
+
+/// Run a subcommand to completion and return the process exit code
+pub fn run(command: Command) -> i32 {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("error: failed to start async runtime: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    match runtime.block_on(execute(command)) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            exit_code(&e)
+        }
+    }
+}
+
+/// Exit code for an error that ended a command
+fn exit_code(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<YtdlError>() {
+        Some(
+            YtdlError::InvalidUrl(_)
+            | YtdlError::InvalidSetting(_)
+            | YtdlError::NotFound(_),
+        ) => EXIT_USAGE,
+        Some(
+            YtdlError::ExecutableMissing
+            | YtdlError::InstallFailed(_)
+            | YtdlError::InvalidVersion(_)
+            | YtdlError::UnsupportedVersion { .. },
+        ) => EXIT_YTDLP,
+        _ => EXIT_FAILURE,
+    }
+}
+
+async fn execute(command: Command) -> Result<i32> {
+    let settings = Settings::load()?;
+
+    match command {
+        Command::Add { urls, options } => {
+            let state = open_queue(settings).await;
+            let ids = queue(&state, &urls, &options).await?;
+            println!("Queued {} download(s)", ids.len());
+            Ok(EXIT_OK)
+        }
+        Command::List { json, groups: true } => {
+            let state = open_queue(settings).await;
+            list_groups(&state.get_groups().await, json)?;
+            Ok(EXIT_OK)
+        }
+        Command::List { json, groups: false } => {
+            let state = open_queue(settings).await;
+            list(&state.get_downloads().await, json)?;
+            Ok(EXIT_OK)
+        }
+        Command::Info { url, json } => info(&settings, &url, json).await,
+        Command::Formats { url } => formats(&settings, &url).await,
+        Command::Download { urls, options } => {
+            let state = open_queue(settings).await;
+            download(&state, &urls, &options).await
+        }
+        Command::Settings { action } => settings_command(settings, action),
+    }
+}
+
+/// App state whose queue only starts what the command asks for
+async fn open_queue(settings: Settings) -> AppState {
+    let state = AppState::with_settings(settings);
+    state.download_manager.write().await.hold_queue();
+    state
+}
+
+/// Queue URLs, expanding collections, and return the ids of the new items
+async fn queue(state: &AppState, urls: &[String], options: &QueueOptions) -> Result<Vec<Uuid>> {
+    let settings = state.get_settings().await;
+    let mode = if options.audio || options.audio_format.is_some() {
+        Some(DownloadMode::Audio {
+            format: options.audio_format.unwrap_or(settings.audio_format),
+            quality: settings.audio_quality.clone(),
+        })
+    } else if let Some(resolution) = &options.resolution {
+        // Validate through the same rules as the settings
+        settings.clone().set_resolution(resolution.clone())?;
+        Some(DownloadMode::Video { resolution: resolution.clone() })
+    } else {
+        None
+    };
+    let selection = match &options.items {
+        Some(items) => PlaylistSelection::parse(items)?,
+        None => PlaylistSelection::All,
+    };
+
+    let mut ids = Vec::new();
+    for url in urls {
+        if !crate::utils::is_valid_youtube_url(url) {
+            return Err(YtdlError::InvalidUrl(url.clone()).into());
+        }
+
+        if crate::utils::is_collection_url(url) {
+            let playlist = state.expand_playlist(url).await?;
+            let group_id = state
+                .add_playlist(url.clone(), &playlist, &selection, mode.clone())
+                .await?;
+            let items: Vec<Uuid> = state
+                .get_downloads()
+                .await
+                .into_iter()
+                .filter(|d| d.group_id == Some(group_id))
+                .map(|d| d.id)
+                .collect();
+            println!(
+                "Queued {} from {}",
+                items.len(),
+                playlist.title.as_deref().unwrap_or(url.as_str())
+            );
+            ids.extend(items);
+        } else {
+            let id = match &mode {
+                Some(mode) => state.add_download_with_mode(url.clone(), mode.clone()).await?,
+                None => state.add_download(url.clone()).await?,
+            };
+            println!("Queued {}", url);
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+fn list(downloads: &[DownloadItem], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(downloads)?);
+        return Ok(());
+    }
+    if downloads.is_empty() {
+        println!("No downloads");
+        return Ok(());
+    }
+
+    println!("{:<8}  {:<11}  {:>6}  TITLE", "ID", "STATUS", "DONE");
+    for download in downloads {
+        println!(
+            "{:<8}  {:<11}  {:>5.1}%  {}",
+            &download.id.to_string()[..8],
+            status_label(&download.status),
+            download.progress * 100.0,
+            download.title.as_deref().unwrap_or(&download.url)
+        );
+    }
+    Ok(())
+}
+
+fn list_groups(groups: &[GroupSummary], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(groups)?);
+        return Ok(());
+    }
+    if groups.is_empty() {
+        println!("No playlist groups");
+        return Ok(());
+    }
+
+    println!("{:<8}  {:>7}  {:>8}  {:>6}  {:>6}  TITLE", "ID", "DONE", "PROGRESS", "FAILED", "ACTIVE");
+    for summary in groups {
+        let progress = &summary.progress;
+        println!(
+            "{:<8}  {:>7}  {:>7.1}%  {:>6}  {:>6}  {}",
+            &summary.group.id.to_string()[..8],
+            format!("{}/{}", progress.completed, progress.total),
+            progress.progress * 100.0,
+            progress.failed,
+            progress.active,
+            summary.group.title.as_deref().unwrap_or(&summary.group.url)
+        );
+    }
+    Ok(())
+}
+
+async fn info(settings: &Settings, url: &str, json: bool) -> Result<i32> {
+    let mut ytdlp = YtDlp::from_settings(settings);
+    ytdlp.initialize().await?;
+    let metadata = ytdlp.get_metadata(url).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&metadata.raw)?);
+        return Ok(EXIT_OK);
+    }
+
+    println!("Title:     {}", metadata.title);
+    if let Some(uploader) = &metadata.uploader {
+        println!("Uploader:  {}", uploader);
+    }
+    if let Some(duration) = metadata.duration {
+        println!("Duration:  {}", format_duration(duration as u64));
+    }
+    if let Some(date) = &metadata.upload_date {
+        println!("Uploaded:  {}", date);
+    }
+    if let Some(views) = metadata.view_count {
+        println!("Views:     {}", views);
+    }
+    if !metadata.chapters.is_empty() {
+        println!("Chapters:  {}", metadata.chapters.len());
+    }
+    let languages = metadata.available_subtitle_languages(false);
+    if !languages.is_empty() {
+        println!("Subtitles: {}", languages.join(", "));
+    }
+    Ok(EXIT_OK)
+}
+
+async fn formats(settings: &Settings, url: &str) -> Result<i32> {
+    let mut ytdlp = YtDlp::from_settings(settings);
+    ytdlp.initialize().await?;
+    let formats = ytdlp.get_formats(url).await?;
+
+    println!("{:<10}  {:<5}  {:<11}  {:>4}  {:>10}  NOTE", "ID", "EXT", "RESOLUTION", "FPS", "SIZE");
+    for format in formats {
+        println!(
+            "{:<10}  {:<5}  {:<11}  {:>4}  {:>10}  {}",
+            format.id,
+            format.ext,
+            format.resolution(),
+            format.fps.map(|fps| format!("{:.0}", fps)).unwrap_or_default(),
+            format.size().map(format_bytes).unwrap_or_default(),
+            format.note.as_deref().unwrap_or_default()
+        );
+    }
+    Ok(EXIT_OK)
+}
+
+/// Download the given URLs, or the whole queue, showing progress until done
+async fn download(state: &AppState, urls: &[String], options: &QueueOptions) -> Result<i32> {
+    let targets = if urls.is_empty() {
+        let mut manager = state.download_manager.write().await;
+        let queued: Vec<DownloadItem> = manager
+            .get_downloads()
+            .into_iter()
+            .filter(|d| matches!(d.status, DownloadStatus::Pending | DownloadStatus::Paused))
+            .collect();
+        for download in queued.iter().filter(|d| d.status == DownloadStatus::Paused) {
+            manager.resume_download(download.id)?;
+        }
+        queued.into_iter().map(|d| d.id).collect()
+    } else {
+        queue(state, urls, options).await?
+    };
+
+    if targets.is_empty() {
+        println!("Nothing to download");
+        return Ok(EXIT_OK);
+    }
+
+    // Subscribe before starting so no event is missed
+    let mut events = state.subscribe().await;
+    let mut downloads = tracked(state, &targets).await;
+    state
+        .download_manager
+        .write()
+        .await
+        .start_selected_downloads(targets.iter().copied());
+
+    let mut display = ProgressDisplay::new();
+    let mut redraw = tokio::time::interval(Duration::from_millis(100));
+    let mut dirty = true;
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    let finished = loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if targets.contains(&event.id()) => {
+                    event.apply(&mut downloads);
+                    dirty = true;
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => {
+                    downloads = tracked(state, &targets).await;
+                    dirty = true;
+                }
+                Err(RecvError::Closed) => break ordered(&downloads),
+            },
+            _ = redraw.tick(), if dirty => {
+                dirty = false;
+                let current = ordered(&downloads);
+                display.render(&current);
+                if current.iter().all(|d| is_finished(&d.status)) {
+                    break current;
+                }
+            }
+            _ = &mut ctrl_c => {
+                let mut manager = state.download_manager.write().await;
+                for id in &targets {
+                    let _ = manager.pause_download(*id);
+                }
+                drop(manager);
+
+                // Give download tasks a moment to kill their yt-dlp process trees
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                eprintln!("\nInterrupted; unfinished downloads were paused and resume with `ytdl-mini download`");
+                return Ok(EXIT_INTERRUPTED);
+            }
+        }
+    };
+
+    let failed: Vec<&DownloadItem> = finished
+        .iter()
+        .filter(|d| !matches!(d.status, DownloadStatus::Success))
+        .collect();
+    println!("{} downloaded, {} failed", finished.len() - failed.len(), failed.len());
+    for download in &finished {
+        if let Some(collision) = download.collision {
+            println!("  {}: {}", download.title.as_deref().unwrap_or(&download.url), collision.description());
+        }
+    }
+    for download in &failed {
+        let reason = match &download.status {
+            DownloadStatus::Failed(error) => error.as_str(),
+            status => status_label(status),
+        };
+        eprintln!("  {}: {}", download.title.as_deref().unwrap_or(&download.url), reason);
+    }
+
+    Ok(if failed.is_empty() { EXIT_OK } else { EXIT_FAILURE })
+}
+
+/// Current state of the given downloads
+async fn tracked(state: &AppState, ids: &[Uuid]) -> HashMap<Uuid, DownloadItem> {
+    state
+        .get_downloads()
+        .await
+        .into_iter()
+        .filter(|d| ids.contains(&d.id))
+        .map(|d| (d.id, d))
+        .collect()
+}
+
+/// Downloads in queue order
+fn ordered(downloads: &HashMap<Uuid, DownloadItem>) -> Vec<DownloadItem> {
+    let mut downloads: Vec<DownloadItem> = downloads.values().cloned().collect();
+    downloads.sort_by_key(|d| d.created_at);
+    downloads
+}
+
+fn is_finished(status: &DownloadStatus) -> bool {
+    matches!(
+        status,
+        DownloadStatus::Success | DownloadStatus::Failed(_) | DownloadStatus::Cancelled
+    )
+}
+
+fn status_label(status: &DownloadStatus) -> &'static str {
+    match status {
+        DownloadStatus::Pending => "queued",
+        DownloadStatus::Downloading => "downloading",
+        DownloadStatus::Success => "done",
+        DownloadStatus::Failed(_) => "failed",
+        DownloadStatus::Cancelled => "cancelled",
+        DownloadStatus::Paused => "paused",
+    }
+}
+
+fn settings_command(mut settings: Settings, action: SettingsAction) -> Result<i32> {
+    match action {
+        SettingsAction::Get { key: None } => {
+            println!("{}", serde_json::to_string_pretty(&settings)?);
+        }
+        SettingsAction::Get { key: Some(key) } => match settings.get_value(&key)? {
+            // Print plain strings without JSON quotes so they can be used in scripts
+            serde_json::Value::String(value) => println!("{}", value),
+            value => println!("{}", serde_json::to_string_pretty(&value)?),
+        },
+        SettingsAction::Set { key, value } => {
+            settings.set_value(&key, &value)?;
+            settings.save()?;
+        }
+    }
+    Ok(EXIT_OK)
+}
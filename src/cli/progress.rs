@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use uuid::Uuid;
+
+use crate::core::{DownloadItem, DownloadStatus};
+use crate::utils::{format_bytes, format_duration};
+
+const TITLE_WIDTH: usize = 40;
+const BAR_WIDTH: usize = 20;
+
+/// Progress output for the `download` command
+///
+/// On a terminal every download gets a line that is redrawn in place. When
+/// output is piped, a line is printed each time a download changes status.
+pub struct ProgressDisplay {
+    interactive: bool,
+    lines_drawn: usize,
+    last_status: HashMap<Uuid, DownloadStatus>,
+}
+
+impl ProgressDisplay {
+    pub fn new() -> Self {
+        Self {
+            interactive: std::io::stdout().is_terminal(),
+            lines_drawn: 0,
+            last_status: HashMap::new(),
+        }
+    }
+
+    pub fn render(&mut self, downloads: &[DownloadItem]) {
+        let mut stdout = std::io::stdout().lock();
+
+        if self.interactive {
+            if self.lines_drawn > 0 {
+                // Move back up to redraw the previous frame
+                let _ = write!(stdout, "\x1b[{}A", self.lines_drawn);
+            }
+            for download in downloads {
+                let _ = writeln!(stdout, "\x1b[2K{}", progress_line(download));
+            }
+            self.lines_drawn = downloads.len();
+        } else {
+            for download in downloads {
+                if self.last_status.get(&download.id) != Some(&download.status) {
+                    let _ = writeln!(stdout, "{}", progress_line(download));
+                    self.last_status.insert(download.id, download.status.clone());
+                }
+            }
+        }
+        let _ = stdout.flush();
+    }
+}
+
+/// One line describing a download, e.g. `Title  [#####.....]  50.0%  1.2 MiB/s  ETA 0:42`
+fn progress_line(download: &DownloadItem) -> String {
+    let title = truncate(download.title.as_deref().unwrap_or(&download.url), TITLE_WIDTH);
+
+    let state = match &download.status {
+        DownloadStatus::Downloading => {
+            let filled = ((download.progress.clamp(0.0, 1.0) * BAR_WIDTH as f32) as usize).min(BAR_WIDTH);
+            let mut line = format!(
+                "[{}{}] {:>5.1}%",
+                "#".repeat(filled),
+                ".".repeat(BAR_WIDTH - filled),
+                download.progress * 100.0
+            );
+            if let Some(details) = &download.progress_details {
+                if let Some(speed) = details.speed {
+                    line.push_str(&format!("  {}/s", format_bytes(speed as u64)));
+                }
+                if let Some(eta) = details.eta {
+                    line.push_str(&format!("  ETA {}", format_duration(eta)));
+                }
+            }
+            line
+        }
+        DownloadStatus::Pending => "queued".to_string(),
+        DownloadStatus::Success => match download.collision {
+            Some(collision) => format!("done, {}", collision.description()),
+            None => "done".to_string(),
+        },
+        DownloadStatus::Failed(_) => "failed".to_string(),
+        DownloadStatus::Cancelled => "cancelled".to_string(),
+        DownloadStatus::Paused => "paused".to_string(),
+    };
+
+    format!("{:<width$}  {}", title, state, width = TITLE_WIDTH)
+}
+
+/// Shorten `text` to at most `width` characters, marking the cut with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut short: String = text.chars().take(width - 1).collect();
+        short.push('…');
+        short
+    }
+}
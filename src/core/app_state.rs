@@ -1,10 +1,13 @@
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-use super::{DownloadManager, Settings};
+use super::{
+    DownloadEvent, DownloadManager, DownloadMode, Playlist, PlaylistSelection, Settings,
+    UpdateChannel, YtDlp, YtDlpVersion, YtdlError,
+};
 
 /// Main application state
 #[derive(Clone)]
@@ -16,25 +19,139 @@ pub struct AppState {
 
 impl AppState {
     pub fn new() -> Self {
+        Self::with_settings(Settings::default())
+    }
+
+    /// Create the app state around already loaded settings
+    pub fn with_settings(settings: Settings) -> Self {
+        let settings = Arc::new(RwLock::new(settings));
+
+        Self {
+            download_manager: DownloadManager::new_shared(settings.clone()),
+            settings,
+            current_url: Arc::new(RwLock::new(String::new())),
+        }
+    }
+
+    /// App state with default settings and a queue that is never saved to disk
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        let settings = Arc::new(RwLock::new(Settings::default()));
+
         Self {
-            download_manager: Arc::new(RwLock::new(DownloadManager::new())),
-            settings: Arc::new(RwLock::new(Settings::default())),
+            download_manager: DownloadManager::new_shared_in_memory(settings.clone()),
+            settings,
             current_url: Arc::new(RwLock::new(String::new())),
         }
     }
 
     /// Add a new download to the queue
-    pub async fn add_download(&self, url: String) -> Result<Uuid, anyhow::Error> {
+    ///
+    /// Playlist, channel and mix URLs are expanded and every entry is queued;
+    /// the returned id is then the id of the new group.
+    pub async fn add_download(&self, url: String) -> Result<Uuid, YtdlError> {
+        if crate::utils::is_collection_url(&url) {
+            let playlist = self.expand_playlist(&url).await?;
+            return self
+                .add_playlist(url, &playlist, &PlaylistSelection::All, None)
+                .await;
+        }
+
         let mut manager = self.download_manager.write().await;
         manager.add_download(url).await
     }
 
+    /// List the entries of a playlist, channel or mix so a subset can be picked
+    pub async fn expand_playlist(&self, url: &str) -> Result<Playlist, YtdlError> {
+        let mut ytdlp = YtDlp::from_settings(&*self.settings.read().await);
+        ytdlp.initialize().await?;
+        ytdlp.get_playlist(url).await
+    }
+
+    /// Queue the selected entries of an expanded playlist as one group
+    pub async fn add_playlist(
+        &self,
+        url: String,
+        playlist: &Playlist,
+        selection: &PlaylistSelection,
+        mode: Option<DownloadMode>,
+    ) -> Result<Uuid, YtdlError> {
+        let entries = playlist.select(selection);
+        let mut manager = self.download_manager.write().await;
+        manager.add_group(url, playlist.title.clone(), entries, mode)
+    }
+
+    /// Add a new download that uses its own mode instead of the one from settings
+    pub async fn add_download_with_mode(
+        &self,
+        url: String,
+        mode: DownloadMode,
+    ) -> Result<Uuid, YtdlError> {
+        let mut manager = self.download_manager.write().await;
+        manager.add_download_with_mode(url, Some(mode)).await
+    }
+
+    /// Start downloads left pending by a previous session
+    pub async fn start_pending_downloads(&self) {
+        let mut manager = self.download_manager.write().await;
+        manager.start_pending_downloads();
+    }
+
+    /// Receive download lifecycle events from now on
+    pub async fn subscribe(&self) -> broadcast::Receiver<DownloadEvent> {
+        self.download_manager.read().await.subscribe()
+    }
+
     /// Get all downloads
     pub async fn get_downloads(&self) -> Vec<super::DownloadItem> {
         let manager = self.download_manager.read().await;
         manager.get_downloads()
     }
 
+    /// Get all playlist groups with their progress, newest first
+    pub async fn get_groups(&self) -> Vec<super::GroupSummary> {
+        let manager = self.download_manager.read().await;
+        manager.get_group_summaries()
+    }
+
+    /// Check for a newer yt-dlp if enabled in settings, logging the result
+    pub async fn check_for_updates_on_startup(&self) {
+        let settings = self.get_settings().await;
+        if !settings.auto_check_updates {
+            return;
+        }
+
+        let mut ytdlp = YtDlp::from_settings(&settings);
+        let check = match ytdlp.initialize().await {
+            Ok(()) => ytdlp.check_for_update().await,
+            Err(e) => Err(e),
+        };
+        match check {
+            Ok(check) if check.update_available() => log::info!(
+                "yt-dlp {} is available on the {} channel (installed: {})",
+                check.latest.version,
+                check.channel.as_str(),
+                check.current
+            ),
+            Ok(check) => log::info!("yt-dlp {} is up to date", check.current),
+            Err(e) => log::warn!("Failed to check for yt-dlp updates: {}", e),
+        }
+    }
+
+    /// Update yt-dlp to the latest build of `channel` and remember the channel
+    pub async fn update_ytdlp(&self, channel: UpdateChannel) -> Result<YtDlpVersion, YtdlError> {
+        let mut ytdlp = YtDlp::from_settings(&*self.settings.read().await);
+        ytdlp.initialize().await?;
+        let version = ytdlp.update(channel).await?;
+
+        let mut settings = self.settings.write().await;
+        if settings.update_channel != channel {
+            settings.update_channel = channel;
+            settings.save()?;
+        }
+        Ok(version)
+    }
+
     /// Update the current URL input
     pub async fn set_current_url(&self, url: String) {
         let mut current_url = self.current_url.write().await;
@@ -48,9 +165,12 @@ impl AppState {
     }
 
     /// Update settings
+    ///
+    /// The download manager picks up the new concurrency limit immediately.
     pub async fn update_settings(&self, new_settings: Settings) {
-        let mut settings = self.settings.write().await;
-        *settings = new_settings;
+        let max_concurrent = new_settings.max_concurrent_downloads;
+        *self.settings.write().await = new_settings;
+        self.download_manager.write().await.set_max_concurrent(max_concurrent);
     }
 
     /// Get current settings
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use super::template::escape;
+
+/// Added to the name of a download that will overwrite an existing file
+const OVERWRITE_SUFFIX: &str = ".ytdl-mini-new";
+
+/// What to do when the file a download would create already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// Leave the existing file and mark the download as already present
+    #[default]
+    Skip,
+    /// Replace the existing file once the new download has succeeded
+    Overwrite,
+    /// Save as `name (1).ext`, `name (2).ext`, ...
+    Rename,
+    /// Save as `name [video id].ext`
+    AppendId,
+}
+
+impl CollisionPolicy {
+    pub const ALL: [CollisionPolicy; 4] = [
+        CollisionPolicy::Skip,
+        CollisionPolicy::Overwrite,
+        CollisionPolicy::Rename,
+        CollisionPolicy::AppendId,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CollisionPolicy::Skip => "Skip",
+            CollisionPolicy::Overwrite => "Overwrite",
+            CollisionPolicy::Rename => "Rename",
+            CollisionPolicy::AppendId => "Append video id",
+        }
+    }
+}
+
+/// How a download dealt with an existing file, as applied by its [`CollisionPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionOutcome {
+    Skipped,
+    Overwritten,
+    Renamed,
+    IdAppended,
+}
+
+impl CollisionOutcome {
+    pub fn description(&self) -> &'static str {
+        match self {
+            CollisionOutcome::Skipped => "skipped, already present",
+            CollisionOutcome::Overwritten => "replaced the existing file",
+            CollisionOutcome::Renamed => "renamed, a file with the same name exists",
+            CollisionOutcome::IdAppended => "video id added, a file with the same name exists",
+        }
+    }
+}
+
+/// An existing file that a download planned as `planned` would collide with
+///
+/// Post-processing, such as audio extraction, can change the planned
+/// extension, so any of `extensions` counts; other files with the same name,
+/// such as a thumbnail or a different mode's download, don't.
+pub fn existing_file(planned: &Path, extensions: &[String]) -> Option<PathBuf> {
+    let dir = planned.parent()?;
+    let stem = planned.file_stem()?;
+
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            let extension = path.extension().and_then(OsStr::to_str).map(str::to_lowercase);
+            path.is_file()
+                && path.file_stem() == Some(stem)
+                && extension.is_some_and(|extension| extensions.contains(&extension))
+        })
+}
+
+/// `--output` value that avoids an existing file, and how it does so
+///
+/// `planned` is where yt-dlp would save the download and `extensions` the
+/// extensions it can end up with. Overwriting downloads under a temporary
+/// name first, see [`replace_existing`]. Returns `None` for skipping or if
+/// nothing is in the way.
+pub fn avoid_collision(
+    planned: &Path,
+    extensions: &[String],
+    policy: CollisionPolicy,
+    video_id: Option<&str>,
+) -> Option<(String, CollisionOutcome)> {
+    existing_file(planned, extensions)?;
+    let dir = planned.parent()?;
+    let stem = planned.file_stem()?.to_string_lossy();
+    // The extension is left to yt-dlp so post-processing can still change it
+    let output = |name: &str| escape(&dir.join(format!("{}.", name)).to_string_lossy()) + "%(ext)s";
+
+    match policy {
+        CollisionPolicy::Rename => (1..)
+            .map(|n| format!("{} ({})", stem, n))
+            .find(|name| existing_file(&dir.join(format!("{}.x", name)), extensions).is_none())
+            .map(|name| (output(&name), CollisionOutcome::Renamed)),
+        CollisionPolicy::AppendId => {
+            // If even that name is taken, yt-dlp skips the download
+            let name = format!("{} [{}]", stem, video_id?);
+            Some((output(&name), CollisionOutcome::IdAppended))
+        }
+        CollisionPolicy::Overwrite => {
+            let name = format!("{}{}", stem, OVERWRITE_SUFFIX);
+            Some((output(&name), CollisionOutcome::Overwritten))
+        }
+        CollisionPolicy::Skip => None,
+    }
+}
+
+/// Move a finished download over the file it was overwriting
+///
+/// The new file keeps the existing file's name with its own extension.
+/// Returns where it ended up.
+pub fn replace_existing(existing: &Path, downloaded: &Path) -> std::io::Result<PathBuf> {
+    let mut target = existing.to_path_buf();
+    if let Some(extension) = downloaded.extension() {
+        target.set_extension(extension);
+    }
+    std::fs::rename(downloaded, &target)?;
+    if target != existing {
+        std::fs::remove_file(existing)?;
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_utils::TempDir;
+
+    fn extensions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|extension| extension.to_string()).collect()
+    }
+
+    #[test]
+    fn test_existing_file_matches_output_extensions_only() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        let planned = dir.join("Video.webm");
+        let audio = extensions(&["mp3"]);
+        assert_eq!(existing_file(&planned, &audio), None);
+
+        for name in ["Video.webm.part", "Video.en.vtt", "Video.mp4", "Video.jpg", "Video.description"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(existing_file(&planned, &audio), None);
+        assert_eq!(existing_file(&planned, &extensions(&["mp4", "webm"])), Some(dir.join("Video.mp4")));
+
+        std::fs::write(dir.join("Video.MP3"), b"").unwrap();
+        assert_eq!(existing_file(&planned, &audio), Some(dir.join("Video.MP3")));
+    }
+
+    #[test]
+    fn test_avoid_collision() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        let planned = dir.join("100% Video.mp4");
+        let video = extensions(&["mp4", "mkv"]);
+        assert_eq!(avoid_collision(&planned, &video, CollisionPolicy::Rename, None), None);
+
+        std::fs::write(&planned, b"").unwrap();
+        std::fs::write(dir.join("100% Video (1).mkv"), b"").unwrap();
+
+        let (output, outcome) = avoid_collision(&planned, &video, CollisionPolicy::Rename, None).unwrap();
+        assert_eq!(outcome, CollisionOutcome::Renamed);
+        assert_eq!(output, escape(&dir.join("100% Video (2).").to_string_lossy()) + "%(ext)s");
+        assert!(output.contains("100%% Video (2)"));
+
+        let (output, outcome) =
+            avoid_collision(&planned, &video, CollisionPolicy::AppendId, Some("abc")).unwrap();
+        assert_eq!(outcome, CollisionOutcome::IdAppended);
+        assert!(output.ends_with("100%% Video [abc].%(ext)s"));
+
+        let (output, outcome) = avoid_collision(&planned, &video, CollisionPolicy::Overwrite, None).unwrap();
+        assert_eq!(outcome, CollisionOutcome::Overwritten);
+        assert!(output.ends_with("100%% Video.ytdl-mini-new.%(ext)s"));
+
+        assert_eq!(avoid_collision(&planned, &video, CollisionPolicy::Skip, None), None);
+        assert_eq!(avoid_collision(&planned, &extensions(&["mp3"]), CollisionPolicy::Rename, None), None);
+    }
+
+    #[test]
+    fn test_replace_existing() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        std::fs::write(dir.join("Video.mp4"), b"old").unwrap();
+        std::fs::write(dir.join("Video.ytdl-mini-new.mp4"), b"new").unwrap();
+        std::fs::write(dir.join("Song.m4a"), b"old").unwrap();
+        std::fs::write(dir.join("Song.ytdl-mini-new.mp3"), b"new").unwrap();
+
+        let replaced = replace_existing(&dir.join("Video.mp4"), &dir.join("Video.ytdl-mini-new.mp4")).unwrap();
+        assert_eq!(replaced, dir.join("Video.mp4"));
+        assert_eq!(std::fs::read(&replaced).unwrap(), b"new");
+
+        let replaced = replace_existing(&dir.join("Song.m4a"), &dir.join("Song.ytdl-mini-new.mp3")).unwrap();
+        assert_eq!(replaced, dir.join("Song.mp3"));
+        assert!(!dir.join("Song.m4a").exists());
+        assert!(!dir.join("Song.ytdl-mini-new.mp3").exists());
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::process::Command;
+
+use super::YtdlError;
+
+/// Flags ytdl-mini sets itself, which extra arguments may not override
+const RESERVED_FLAGS: &[&str] = &[
+    "-o", "--output", "-P", "--paths",
+    "-f", "--format", "-S", "--format-sort", "--merge-output-format", "--remux-video",
+    "-x", "--extract-audio", "--audio-format", "--audio-quality",
+    "--write-subs", "--write-auto-subs", "--sub-langs", "--sub-format", "--convert-subs", "--embed-subs",
+    "--embed-thumbnail", "--convert-thumbnails",
+    "--no-playlist", "--yes-playlist", "--flat-playlist",
+    "-j", "--dump-json", "-J", "--dump-single-json", "-O", "--print", "--print-to-file",
+    "-s", "--simulate", "--skip-download", "--no-download",
+    "-c", "--continue", "--no-continue",
+    "-w", "--no-overwrites", "--force-overwrites", "--no-force-overwrites", "--no-post-overwrites",
+    "--newline", "--progress-template", "--progress", "--no-progress",
+    "-q", "--quiet", "--no-quiet",
+    "-U", "--update", "--update-to", "--version",
+];
+
+/// Short flags that take a value, which may be attached as in `-N4`
+const SHORT_FLAGS_WITH_VALUE: &str = "aup2oPfSONrRIt";
+
+/// How to run yt-dlp, e.g. `C:\Tools\yt-dlp.exe` or `python -m yt_dlp`
+///
+/// Stored in settings as a single command line; arguments containing spaces
+/// can be quoted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct YtDlpCommand {
+    pub program: PathBuf,
+    /// Arguments that select yt-dlp, such as `-m yt_dlp`
+    pub args: Vec<String>,
+}
+
+impl YtDlpCommand {
+    /// Run a yt-dlp executable directly
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Check that the program exists, looking bare names up on PATH
+    pub fn resolve(&self) -> Result<Self, YtdlError> {
+        let not_found = || {
+            YtdlError::InvalidSetting(format!("yt-dlp command not found: {}", self.program.display()))
+        };
+
+        let program = if self.program.components().count() > 1 || self.program.is_absolute() {
+            self.program.is_file().then(|| self.program.clone()).ok_or_else(not_found)?
+        } else {
+            which::which(&self.program).map_err(|_| not_found())?
+        };
+
+        Ok(Self { program, args: self.args.clone() })
+    }
+
+    /// A process builder for this command
+    pub(crate) fn to_command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+}
+
+impl FromStr for YtDlpCommand {
+    type Err = YtdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = split_args(s)?.into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| YtdlError::InvalidSetting("The yt-dlp command is empty".to_string()))?;
+
+        Ok(Self {
+            program: PathBuf::from(program),
+            args: words.collect(),
+        })
+    }
+}
+
+impl TryFrom<String> for YtDlpCommand {
+    type Error = YtdlError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<YtDlpCommand> for String {
+    fn from(command: YtDlpCommand) -> Self {
+        command.to_string()
+    }
+}
+
+impl fmt::Display for YtDlpCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let program = self.program.to_string_lossy();
+        let words = std::iter::once(program.as_ref()).chain(self.args.iter().map(|arg| arg.as_str()));
+
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            if word.is_empty() || word.contains(char::is_whitespace) {
+                write!(f, "\"{}\"", word)?;
+            } else {
+                f.write_str(word)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a command line into words, honouring single and double quotes
+///
+/// Backslashes are kept as-is so Windows paths need no escaping.
+pub fn split_args(s: &str) -> Result<Vec<String>, YtdlError> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in s.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(YtdlError::InvalidSetting(format!("Unclosed quote in '{}'", s)));
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Reject extra arguments that would override flags ytdl-mini controls
+pub fn validate_extra_args(args: &[String]) -> Result<(), YtdlError> {
+    for arg in args {
+        if arg.trim().is_empty() {
+            return Err(YtdlError::InvalidSetting("Extra yt-dlp arguments can't be empty".to_string()));
+        }
+
+        let mut flags = short_flags(arg).chain(long_flag(arg));
+        if flags.any(|flag| RESERVED_FLAGS.contains(&flag.as_str())) {
+            return Err(YtdlError::InvalidSetting(format!(
+                "'{}' is set by ytdl-mini and can't be passed as an extra argument",
+                arg
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The long flag an argument sets, without an `=value`
+fn long_flag(arg: &str) -> Option<String> {
+    arg.starts_with("--").then(|| arg.split('=').next().unwrap_or(arg).to_string())
+}
+
+/// The short flags an argument sets
+///
+/// Short flags can be bundled, as in `-sj`, and carry their value directly,
+/// as in `-ofile.mp4`; everything after a flag that takes a value is that value.
+fn short_flags(arg: &str) -> impl Iterator<Item = String> + '_ {
+    let bundle = arg.strip_prefix('-').filter(|_| !arg.starts_with("--")).unwrap_or("");
+    let mut value_follows = false;
+    bundle.chars().map_while(move |c| {
+        if value_follows {
+            return None;
+        }
+        value_follows = SHORT_FLAGS_WITH_VALUE.contains(c);
+        Some(format!("-{}", c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let command: YtDlpCommand = "python -m yt_dlp".parse().unwrap();
+        assert_eq!(command.program, PathBuf::from("python"));
+        assert_eq!(command.args, ["-m", "yt_dlp"]);
+
+        let command: YtDlpCommand = r#""C:\Program Files\yt-dlp\yt-dlp.exe""#.parse().unwrap();
+        assert_eq!(command.program, PathBuf::from(r"C:\Program Files\yt-dlp\yt-dlp.exe"));
+        assert!(command.args.is_empty());
+        assert_eq!(command.to_string(), r#""C:\Program Files\yt-dlp\yt-dlp.exe""#);
+
+        assert!("  ".parse::<YtDlpCommand>().is_err());
+        assert!("'python -m".parse::<YtDlpCommand>().is_err());
+    }
+
+    #[test]
+    fn test_validate_extra_args() {
+        let allowed = [
+            "--proxy", "socks5://127.0.0.1:1080", "--cookies-from-browser=firefox", "-N", "4", "-N4",
+            "-ik", "-rSj",
+        ];
+        assert!(validate_extra_args(&allowed.map(String::from)).is_ok());
+
+        for arg in [
+            "--output", "--format=best", "-f", "-ofile.mp4", "--print", "--no-playlist", "", "-sj",
+            "-io", "-wxk", "-iw", "--force-overwrites", "--no-force-overwrites", "--no-post-overwrites",
+        ] {
+            assert!(validate_extra_args(&[arg.to_string()]).is_err(), "{}", arg);
+        }
+    }
+}
@@ -1,10 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use tokio::sync::mpsc;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use tokio::sync::{broadcast, oneshot, RwLock};
+
+use super::events::EVENT_CAPACITY;
+use super::{
+    CollisionOutcome, CompletedDownload, DownloadEvent, DownloadMode, DownloadOptions,
+    DownloadProgress, FailureKind, PlaylistEntry, Settings, StopMode, ThumbnailCache,
+    VideoMetadata, YtDlp, YtdlError,
+};
 
 /// Download status enumeration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -13,6 +21,8 @@ pub enum DownloadStatus {
     Downloading,
     Success,
     Failed(String),
+    Cancelled,
+    Paused,
 }
 
 /// Individual download item
@@ -24,7 +34,46 @@ pub struct DownloadItem {
     pub status: DownloadStatus,
     pub created_at: DateTime<Utc>,
     pub progress: f32, // 0.0 to 1.0
+    /// Latest detailed progress report (speed, ETA, sizes) while downloading
+    #[serde(default)]
+    pub progress_details: Option<DownloadProgress>,
+    /// The final file, once the download has completed
     pub file_path: Option<String>,
+    /// Size of the final file in bytes
+    #[serde(default)]
+    pub file_size: Option<u64>,
+    /// Lowercase hex SHA-256 of the final file
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Per-download mode; `None` uses the mode from settings
+    #[serde(default)]
+    pub mode: Option<DownloadMode>,
+    /// Playlist or channel group this item was expanded from
+    #[serde(default)]
+    pub group_id: Option<Uuid>,
+    /// Cached thumbnail image, once it has been fetched
+    #[serde(default)]
+    pub thumbnail_path: Option<String>,
+    /// Every yt-dlp run for this item, including retries
+    #[serde(default)]
+    pub attempts: Vec<DownloadAttempt>,
+    /// How an existing file with the same name was dealt with
+    #[serde(default)]
+    pub collision: Option<CollisionOutcome>,
+    /// Files a paused download has partly written, removed if it is cancelled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partial_files: Vec<PathBuf>,
+}
+
+/// The outcome of one yt-dlp run for a download
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadAttempt {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Error message if the attempt failed
+    pub error: Option<String>,
+    /// Classification of the failure, if yt-dlp reported one
+    pub failure: Option<FailureKind>,
 }
 
 impl DownloadItem {
@@ -36,68 +85,352 @@ impl DownloadItem {
             status: DownloadStatus::Pending,
             created_at: Utc::now(),
             progress: 0.0,
+            progress_details: None,
             file_path: None,
+            file_size: None,
+            sha256: None,
+            mode: None,
+            group_id: None,
+            thumbnail_path: None,
+            attempts: Vec::new(),
+            collision: None,
+            partial_files: Vec::new(),
         }
     }
 }
 
+/// A playlist, channel or mix whose entries were queued as separate items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadGroup {
+    pub id: Uuid,
+    /// The playlist or channel URL the group was expanded from
+    pub url: String,
+    pub title: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Aggregate progress over the items of a group
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupProgress {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub active: usize,
+    /// Average progress of all items, 0.0 to 1.0
+    pub progress: f32,
+}
+
+impl GroupProgress {
+    /// Aggregate the given items, which should all belong to one group
+    pub fn of<'a>(items: impl IntoIterator<Item = &'a DownloadItem>) -> Self {
+        let items: Vec<_> = items.into_iter().collect();
+        let count = |f: fn(&DownloadStatus) -> bool| items.iter().filter(|d| f(&d.status)).count();
+
+        Self {
+            total: items.len(),
+            completed: count(|s| *s == DownloadStatus::Success),
+            failed: count(|s| matches!(s, DownloadStatus::Failed(_))),
+            active: count(|s| *s == DownloadStatus::Downloading),
+            progress: if items.is_empty() {
+                0.0
+            } else {
+                items.iter().map(|d| d.progress).sum::<f32>() / items.len() as f32
+            },
+        }
+    }
+}
+
+/// A group together with the progress of its items
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupSummary {
+    #[serde(flatten)]
+    pub group: DownloadGroup,
+    #[serde(flatten)]
+    pub progress: GroupProgress,
+}
+
+/// On-disk layout of the download history file
+#[derive(Serialize, Deserialize)]
+struct History {
+    downloads: Vec<DownloadItem>,
+    #[serde(default)]
+    groups: Vec<DownloadGroup>,
+}
+
+/// Which pending downloads the manager starts when a slot is free
+#[derive(Debug, Clone, PartialEq)]
+enum StartPolicy {
+    /// Work through the whole queue
+    All,
+    /// Keep everything queued until told to start
+    Held,
+    /// Only start these downloads
+    Only(HashSet<Uuid>),
+}
+
+/// One spawned task working on a download
+///
+/// A download that is stopped and started again gets a new run; results are
+/// only accepted from the run they were started for.
+struct Run {
+    id: Uuid,
+    /// Taken once the run has been told to stop
+    stop: Option<oneshot::Sender<StopMode>>,
+}
+
 /// Download manager handles the queue and processing of downloads
 pub struct DownloadManager {
     downloads: HashMap<Uuid, DownloadItem>,
+    groups: HashMap<Uuid, DownloadGroup>,
     active_downloads: usize,
     max_concurrent: usize,
+    settings: Arc<RwLock<Settings>>,
+    /// Where the queue and history are saved; `None` keeps them in memory only
+    history_path: Option<PathBuf>,
+    /// The latest run of each download whose task hasn't reported back yet
+    runs: HashMap<Uuid, Run>,
+    /// Handle back to the shared manager so spawned tasks can report results
+    handle: Weak<RwLock<DownloadManager>>,
+    start_policy: StartPolicy,
+    /// Lifecycle events for subscribers such as the UI, CLI and API
+    events: broadcast::Sender<DownloadEvent>,
 }
 
 impl DownloadManager {
     pub fn new() -> Self {
+        Self::with_settings(Arc::new(RwLock::new(Settings::default())), Weak::new())
+    }
+
+    /// Create a shared download manager whose download tasks write back into it
+    ///
+    /// The queue and history are restored from, and saved to, `downloads.json`
+    /// in the config directory.
+    pub fn new_shared(settings: Arc<RwLock<Settings>>) -> Arc<RwLock<Self>> {
+        let history_path = Self::history_path()
+            .map_err(|e| log::warn!("Download history will not be saved: {}", e))
+            .ok();
+        Self::shared(settings, history_path)
+    }
+
+    /// Create a shared download manager that neither restores nor saves a history
+    #[cfg(test)]
+    pub(crate) fn new_shared_in_memory(settings: Arc<RwLock<Settings>>) -> Arc<RwLock<Self>> {
+        Self::shared(settings, None)
+    }
+
+    fn shared(settings: Arc<RwLock<Settings>>, history_path: Option<PathBuf>) -> Arc<RwLock<Self>> {
+        Arc::new_cyclic(|handle| {
+            let mut manager = Self::with_settings(settings, handle.clone());
+            // Nothing else holds the settings yet, so this read can't fail
+            if let Ok(settings) = manager.settings.try_read() {
+                manager.max_concurrent = settings.max_concurrent_downloads.max(1);
+            }
+
+            if let Some(path) = history_path {
+                if path.exists() {
+                    if let Err(e) = manager.load_history(&path) {
+                        log::warn!("Could not restore download history: {}", e);
+                    }
+                }
+                manager.history_path = Some(path);
+            }
+
+            RwLock::new(manager)
+        })
+    }
+
+    fn with_settings(settings: Arc<RwLock<Settings>>, handle: Weak<RwLock<DownloadManager>>) -> Self {
         Self {
             downloads: HashMap::new(),
+            groups: HashMap::new(),
             active_downloads: 0,
             max_concurrent: 3,
+            settings,
+            history_path: None,
+            runs: HashMap::new(),
+            handle,
+            start_policy: StartPolicy::All,
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Receive an event for every change to a download from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<DownloadEvent> {
+        self.events.subscribe()
+    }
+
+    /// Broadcast an event; it is fine if nobody is listening
+    fn emit(&self, event: DownloadEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// The event announcing a download's current status
+    fn status_event(&self, id: Uuid) -> Option<DownloadEvent> {
+        let download = self.downloads.get(&id)?;
+        Some(match &download.status {
+            DownloadStatus::Pending => DownloadEvent::Queued { id, item: Box::new(download.clone()) },
+            DownloadStatus::Downloading => DownloadEvent::Started { id },
+            DownloadStatus::Success => DownloadEvent::Completed {
+                id,
+                file_path: download.file_path.clone(),
+                file_size: download.file_size,
+                sha256: download.sha256.clone(),
+                collision: download.collision,
+            },
+            DownloadStatus::Failed(error) => DownloadEvent::Failed {
+                id,
+                error: error.clone(),
+                failure: download.attempts.last().and_then(|attempt| attempt.failure),
+            },
+            DownloadStatus::Cancelled => DownloadEvent::Cancelled { id },
+            DownloadStatus::Paused => DownloadEvent::Paused { id },
+        })
+    }
+
+    /// Broadcast that a download's title or thumbnail changed
+    fn emit_metadata(&self, id: Uuid) {
+        if let Some(download) = self.downloads.get(&id) {
+            self.emit(DownloadEvent::MetadataResolved {
+                id,
+                title: download.title.clone(),
+                thumbnail_path: download.thumbnail_path.clone(),
+            });
         }
     }
 
     /// Add a new download to the queue
-    pub async fn add_download(&mut self, url: String) -> Result<Uuid, anyhow::Error> {
+    pub async fn add_download(&mut self, url: String) -> Result<Uuid, YtdlError> {
+        self.add_download_with_mode(url, None).await
+    }
+
+    /// Add a new download to the queue, overriding the default mode from settings
+    pub async fn add_download_with_mode(
+        &mut self,
+        url: String,
+        mode: Option<DownloadMode>,
+    ) -> Result<Uuid, YtdlError> {
         // Validate URL first
         if !crate::utils::is_valid_youtube_url(&url) {
-            return Err(anyhow::anyhow!("Invalid YouTube URL"));
+            return Err(YtdlError::InvalidUrl(url));
         }
 
-        let download_item = DownloadItem::new(url);
+        let mut download_item = DownloadItem::new(url);
+        download_item.mode = mode;
         let id = download_item.id;
         
-        self.downloads.insert(id, download_item);
+        self.downloads.insert(id, download_item.clone());
+        self.emit(DownloadEvent::Queued { id, item: Box::new(download_item) });
         
         // Try to start download if we have capacity
-        self.try_start_next_download().await;
+        self.try_start_next_download();
+        self.persist();
         
         Ok(id)
     }
 
+    /// Queue the selected entries of a playlist as items of a new group
+    ///
+    /// Returns the group id. Entries keep their playlist order in the queue.
+    pub fn add_group(
+        &mut self,
+        url: String,
+        title: Option<String>,
+        entries: Vec<PlaylistEntry>,
+        mode: Option<DownloadMode>,
+    ) -> Result<Uuid, YtdlError> {
+        if entries.is_empty() {
+            return Err(YtdlError::InvalidState("No playlist entries selected"));
+        }
+
+        let group = DownloadGroup {
+            id: Uuid::new_v4(),
+            url,
+            title,
+            created_at: Utc::now(),
+        };
+        let group_id = group.id;
+
+        for (offset, entry) in entries.into_iter().enumerate() {
+            let mut download_item = DownloadItem::new(entry.url);
+            download_item.title = entry.title;
+            download_item.mode = mode.clone();
+            download_item.group_id = Some(group_id);
+            // Queue order follows creation time, so space entries out to keep playlist order
+            download_item.created_at = group.created_at + chrono::Duration::microseconds(offset as i64);
+            self.downloads.insert(download_item.id, download_item.clone());
+            self.emit(DownloadEvent::Queued { id: download_item.id, item: Box::new(download_item) });
+        }
+        self.groups.insert(group_id, group);
+
+        self.try_start_next_download();
+        self.persist();
+
+        Ok(group_id)
+    }
+
+    /// Get all download groups, newest first
+    pub fn get_groups(&self) -> Vec<DownloadGroup> {
+        let mut groups: Vec<_> = self.groups.values().cloned().collect();
+        groups.sort_by_key(|g| std::cmp::Reverse(g.created_at));
+        groups
+    }
+
+    /// Aggregate progress of a group's items
+    pub fn group_progress(&self, group_id: Uuid) -> Option<GroupProgress> {
+        if !self.groups.contains_key(&group_id) {
+            return None;
+        }
+
+        Some(GroupProgress::of(
+            self.downloads.values().filter(|d| d.group_id == Some(group_id)),
+        ))
+    }
+
+    /// Get all groups with their progress, newest first
+    pub fn get_group_summaries(&self) -> Vec<GroupSummary> {
+        self.get_groups()
+            .into_iter()
+            .filter_map(|group| {
+                let progress = self.group_progress(group.id)?;
+                Some(GroupSummary { group, progress })
+            })
+            .collect()
+    }
+
     /// Get all downloads
     pub fn get_downloads(&self) -> Vec<DownloadItem> {
         let mut downloads: Vec<_> = self.downloads.values().cloned().collect();
-        downloads.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        downloads.sort_by_key(|d| std::cmp::Reverse(d.created_at));
         downloads
     }
 
     /// Update download status
     pub fn update_download_status(&mut self, id: Uuid, status: DownloadStatus) {
         if let Some(download) = self.downloads.get_mut(&id) {
+            let was_active = download.status == DownloadStatus::Downloading;
             download.status = status;
             
-            // If download finished (success or failed), decrement active count
-            if matches!(download.status, DownloadStatus::Success | DownloadStatus::Failed(_)) {
+            // If an active download finished or was stopped, decrement active count
+            if was_active && download.status != DownloadStatus::Downloading {
                 self.active_downloads = self.active_downloads.saturating_sub(1);
             }
+            if let Some(event) = self.status_event(id) {
+                self.emit(event);
+            }
+            self.persist();
         }
     }
 
     /// Update download progress
-    pub fn update_download_progress(&mut self, id: Uuid, progress: f32) {
+    pub fn update_download_progress(&mut self, id: Uuid, progress: DownloadProgress) {
         if let Some(download) = self.downloads.get_mut(&id) {
-            download.progress = progress.clamp(0.0, 1.0);
+            if let Some(fraction) = progress.fraction() {
+                download.progress = fraction.clamp(0.0, 1.0);
+            }
+            download.progress_details = Some(progress.clone());
+
+            let event = DownloadEvent::Progress { id, progress: download.progress, details: progress };
+            self.emit(event);
         }
     }
 
@@ -105,78 +438,755 @@ impl DownloadManager {
     pub fn update_download_title(&mut self, id: Uuid, title: String) {
         if let Some(download) = self.downloads.get_mut(&id) {
             download.title = Some(title);
+            self.emit_metadata(id);
+            self.persist();
         }
     }
 
-    /// Try to start the next pending download
-    async fn try_start_next_download(&mut self) {
-        if self.active_downloads >= self.max_concurrent {
-            return;
+    /// Update the cached thumbnail of a download
+    pub fn update_download_thumbnail(&mut self, id: Uuid, path: PathBuf) {
+        if let Some(download) = self.downloads.get_mut(&id) {
+            download.thumbnail_path = Some(path.to_string_lossy().into_owned());
+            self.emit_metadata(id);
+            self.persist();
         }
+    }
 
-        // Find next pending download
-        let next_download = self.downloads
-            .values()
-            .find(|d| d.status == DownloadStatus::Pending)
-            .map(|d| d.id);
+    /// Record a finished yt-dlp run for a download
+    pub fn record_attempt(&mut self, id: Uuid, attempt: DownloadAttempt) {
+        if let Some(download) = self.downloads.get_mut(&id) {
+            download.attempts.push(attempt);
+            self.persist();
+        }
+    }
+
+    /// Record the outcome of a download task and start the next pending item
+    ///
+    /// `partials` are the files the task wrote, kept on the item if it was
+    /// paused. Results from any run but the latest one of `id` are ignored.
+    fn finish_download(
+        &mut self,
+        id: Uuid,
+        run_id: Uuid,
+        result: Result<CompletedDownload, YtdlError>,
+        partials: Vec<PathBuf>,
+    ) {
+        match self.runs.get(&id) {
+            Some(run) if run.id == run_id => {
+                self.runs.remove(&id);
+            }
+            _ => {
+                log::debug!("Ignoring the result of an earlier run of {}", id);
+                return;
+            }
+        }
+
+        // A stopped download already released its slot; keep its status as is
+        match self.downloads.get_mut(&id) {
+            Some(download) if download.status == DownloadStatus::Downloading => {}
+            // Paused, or resumed or retried while the task was still stopping
+            Some(download)
+                if matches!(download.status, DownloadStatus::Paused | DownloadStatus::Pending) =>
+            {
+                download.partial_files = partials;
+                self.persist();
+                self.try_start_next_download();
+                return;
+            }
+            // Cancelled or removed while the task was still stopping
+            _ => {
+                for partial in &partials {
+                    crate::utils::file_utils::remove_partial_files(partial);
+                }
+                return;
+            }
+        }
+
+        let status = match result {
+            Ok(completed) => {
+                if let Some(download) = self.downloads.get_mut(&id) {
+                    download.progress = 1.0;
+                    download.progress_details = None;
+                    download.collision = completed.collision;
+                    download.file_path = completed.file_path;
+                    download.file_size = completed.file_size;
+                    download.sha256 = completed.sha256;
+                    download.partial_files.clear();
+                }
+                DownloadStatus::Success
+            }
+            Err(e) => {
+                log::error!("Download {} failed: {}", id, e);
+                if let Some(download) = self.downloads.get_mut(&id) {
+                    download.progress_details = None;
+                }
+                DownloadStatus::Failed(e.to_string())
+            }
+        };
 
-        if let Some(id) = next_download {
-            self.start_download(id).await;
+        self.update_download_status(id, status);
+        self.try_start_next_download();
+    }
+
+    /// Start pending downloads until the concurrency limit is reached
+    fn try_start_next_download(&mut self) {
+        while self.active_downloads < self.max_concurrent {
+            match self.next_pending() {
+                Some(id) => self.start_download(id),
+                None => break,
+            }
         }
     }
 
+    /// The oldest pending download that may start now
+    fn next_pending(&self) -> Option<Uuid> {
+        self.downloads
+            .values()
+            .filter(|d| d.status == DownloadStatus::Pending)
+            // A stopped run must report back before the download can start again
+            .filter(|d| !self.runs.contains_key(&d.id))
+            .filter(|d| match &self.start_policy {
+                StartPolicy::All => true,
+                StartPolicy::Held => false,
+                StartPolicy::Only(ids) => ids.contains(&d.id),
+            })
+            .min_by_key(|d| d.created_at)
+            .map(|d| d.id)
+    }
+
+    /// Mark a download as running under a new run, returning the run's id and stop signal
+    fn begin_run(&mut self, id: Uuid) -> Option<(Uuid, oneshot::Receiver<StopMode>)> {
+        let download = self.downloads.get_mut(&id)?;
+        download.status = DownloadStatus::Downloading;
+        self.active_downloads += 1;
+
+        let run_id = Uuid::new_v4();
+        let (stop_tx, stop_rx) = oneshot::channel();
+        self.runs.insert(id, Run { id: run_id, stop: Some(stop_tx) });
+        self.emit(DownloadEvent::Started { id });
+        Some((run_id, stop_rx))
+    }
+
     /// Start a specific download
-    async fn start_download(&mut self, id: Uuid) {
-        if let Some(download) = self.downloads.get_mut(&id) {
-            download.status = DownloadStatus::Downloading;
-            self.active_downloads += 1;
+    fn start_download(&mut self, id: Uuid) {
+        if let Some((run_id, mut stop_rx)) = self.begin_run(id) {
+            let download = &self.downloads[&id];
 
             // Clone necessary data for the async task
             let url = download.url.clone();
-            let download_id = id;
+            let mode = download.mode.clone();
+            let mut partials = download.partial_files.clone();
+            let settings = self.settings.clone();
+            let manager = self.handle.clone();
 
             // Spawn download task
             tokio::spawn(async move {
-                // This is a placeholder for the actual download logic
-                // In a real implementation, this would:
-                // 1. Initialize YT-DLP
-                // 2. Get video metadata
-                // 3. Start the download
-                // 4. Update progress
-                // 5. Handle completion/errors
-
                 log::info!("Starting download for: {}", url);
 
-                // Simulate download process
-                tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                let settings = settings.read().await.clone();
+                let options = settings.download_options(mode);
+                let result = run_download(
+                    &manager,
+                    id,
+                    &url,
+                    &settings,
+                    &options,
+                    &mut stop_rx,
+                    &mut partials,
+                )
+                .await;
 
-                // TODO: Integrate with actual YT-DLP download logic
-                // For now, we'll just mark it as successful
-                log::info!("Download completed for: {}", url);
+                match manager.upgrade() {
+                    Some(manager) => {
+                        manager.write().await.finish_download(id, run_id, result, partials)
+                    }
+                    None => log::warn!("Download manager dropped before {} finished", url),
+                }
             });
+
+            self.persist();
         }
     }
 
     /// Set maximum concurrent downloads
+    ///
+    /// Raising the limit starts waiting downloads right away; lowering it lets
+    /// running downloads finish.
     pub fn set_max_concurrent(&mut self, max: usize) {
         self.max_concurrent = max.max(1); // Ensure at least 1
+        self.try_start_next_download();
+    }
+
+    /// Cancel a pending, paused or in-flight download
+    ///
+    /// Running downloads have their yt-dlp process killed and partial files
+    /// removed, as do paused ones; the freed slot goes to the next pending item.
+    pub fn cancel_download(&mut self, id: Uuid) -> Result<(), YtdlError> {
+        match self.status_of(id)? {
+            DownloadStatus::Pending | DownloadStatus::Downloading | DownloadStatus::Paused => {
+                self.discard_partial_files(id);
+                self.stop_download(id, StopMode::Cancel, DownloadStatus::Cancelled);
+                Ok(())
+            }
+            _ => Err(YtdlError::InvalidState("Download is not pending or in progress")),
+        }
+    }
+
+    /// Pause a pending or in-flight download, keeping its partial files
+    ///
+    /// Paused downloads don't count against the concurrency limit.
+    pub fn pause_download(&mut self, id: Uuid) -> Result<(), YtdlError> {
+        match self.status_of(id)? {
+            DownloadStatus::Pending | DownloadStatus::Downloading => {
+                self.stop_download(id, StopMode::Pause, DownloadStatus::Paused);
+                Ok(())
+            }
+            _ => Err(YtdlError::InvalidState("Download is not pending or in progress")),
+        }
+    }
+
+    /// Put a paused download back in the queue
+    ///
+    /// It starts right away if a slot is free, otherwise it waits its turn; a
+    /// download still being stopped starts once its task has reported back.
+    pub fn resume_download(&mut self, id: Uuid) -> Result<(), YtdlError> {
+        match self.status_of(id)? {
+            DownloadStatus::Paused => {
+                self.update_download_status(id, DownloadStatus::Pending);
+                self.try_start_next_download();
+                Ok(())
+            }
+            _ => Err(YtdlError::InvalidState("Download is not paused")),
+        }
     }
 
-    /// Remove a download
+    /// Queue a failed or cancelled download again
+    ///
+    /// A cancelled download that is still being stopped starts once its task
+    /// has reported back.
+    pub fn retry_download(&mut self, id: Uuid) -> Result<(), YtdlError> {
+        match self.status_of(id)? {
+            DownloadStatus::Failed(_) | DownloadStatus::Cancelled => {
+                if let Some(download) = self.downloads.get_mut(&id) {
+                    download.progress = 0.0;
+                    download.progress_details = None;
+                    download.collision = None;
+                }
+                self.update_download_status(id, DownloadStatus::Pending);
+                self.try_start_next_download();
+                Ok(())
+            }
+            _ => Err(YtdlError::InvalidState("Download has not failed or been cancelled")),
+        }
+    }
+
+    /// Get the current status of a download
+    fn status_of(&self, id: Uuid) -> Result<DownloadStatus, YtdlError> {
+        self.downloads
+            .get(&id)
+            .map(|d| d.status.clone())
+            .ok_or(YtdlError::NotFound(id))
+    }
+
+    /// Signal a running task to stop, move the item to `status` and refill the free slot
+    fn stop_download(&mut self, id: Uuid, mode: StopMode, status: DownloadStatus) {
+        if let Some(stop) = self.runs.get_mut(&id).and_then(|run| run.stop.take()) {
+            let _ = stop.send(mode);
+        }
+        if let Some(download) = self.downloads.get_mut(&id) {
+            download.progress_details = None;
+        }
+        self.update_download_status(id, status);
+        self.try_start_next_download();
+    }
+
+    /// Remove the files a paused download left behind
+    fn discard_partial_files(&mut self, id: Uuid) {
+        if let Some(download) = self.downloads.get_mut(&id) {
+            for partial in std::mem::take(&mut download.partial_files) {
+                crate::utils::file_utils::remove_partial_files(&partial);
+            }
+        }
+    }
+
+    /// Remove a download, stopping it first if it is still running
+    ///
+    /// Partial files of a paused download are removed with it.
     pub fn remove_download(&mut self, id: Uuid) -> Option<DownloadItem> {
-        self.downloads.remove(&id)
+        if self.downloads.get(&id).is_some_and(|d| d.status == DownloadStatus::Downloading) {
+            let _ = self.cancel_download(id);
+        }
+        self.discard_partial_files(id);
+        let removed = self.downloads.remove(&id);
+        if removed.is_some() {
+            self.emit(DownloadEvent::Removed { id });
+        }
+        self.remove_empty_groups();
+        self.persist();
+        removed
     }
 
     /// Clear all completed downloads
     pub fn clear_completed(&mut self) {
-        self.downloads.retain(|_, download| {
-            !matches!(download.status, DownloadStatus::Success)
-        });
+        let completed: Vec<Uuid> = self.downloads
+            .values()
+            .filter(|download| matches!(download.status, DownloadStatus::Success))
+            .map(|download| download.id)
+            .collect();
+        for id in completed {
+            self.downloads.remove(&id);
+            self.emit(DownloadEvent::Removed { id });
+        }
+        self.remove_empty_groups();
+        self.persist();
+    }
+
+    /// Drop groups whose items have all been removed
+    fn remove_empty_groups(&mut self) {
+        let downloads = &self.downloads;
+        self.groups.retain(|id, _| downloads.values().any(|d| d.group_id == Some(*id)));
+    }
+
+    /// Start any pending downloads, e.g. ones restored from a previous session
+    pub fn start_pending_downloads(&mut self) {
+        self.start_policy = StartPolicy::All;
+        self.try_start_next_download();
+    }
+
+    /// Keep new and pending downloads queued instead of starting them
+    ///
+    /// Lets the queue be edited, e.g. from the command line, without
+    /// downloading anything until [`start_pending_downloads`](Self::start_pending_downloads)
+    /// or [`start_selected_downloads`](Self::start_selected_downloads) is called.
+    pub fn hold_queue(&mut self) {
+        self.start_policy = StartPolicy::Held;
+    }
+
+    /// Start only the given pending downloads, leaving the rest of the queue alone
+    pub fn start_selected_downloads(&mut self, ids: impl IntoIterator<Item = Uuid>) {
+        self.start_policy = StartPolicy::Only(ids.into_iter().collect());
+        self.try_start_next_download();
+    }
+
+    /// Save all downloads to `path`, replacing the file atomically
+    pub fn save_history(&self, path: &Path) -> Result<(), YtdlError> {
+        let history = History {
+            downloads: self.get_downloads(),
+            groups: self.get_groups(),
+        };
+        let content = serde_json::to_string_pretty(&history)?;
+        Ok(crate::utils::file_utils::write_atomic(path, content.as_bytes())?)
+    }
+
+    /// Restore downloads saved by [`save_history`](Self::save_history)
+    ///
+    /// Downloads that were running when the app closed come back paused so
+    /// they can resume from their partial files.
+    pub fn load_history(&mut self, path: &Path) -> Result<(), YtdlError> {
+        let content = std::fs::read_to_string(path)?;
+        // Older history files are a bare list of downloads
+        let history = serde_json::from_str::<History>(&content).or_else(|_| {
+            serde_json::from_str::<Vec<DownloadItem>>(&content)
+                .map(|downloads| History { downloads, groups: Vec::new() })
+        })?;
+
+        for group in history.groups {
+            self.groups.insert(group.id, group);
+        }
+        for mut item in history.downloads {
+            if item.status == DownloadStatus::Downloading {
+                item.status = DownloadStatus::Paused;
+            }
+            item.progress_details = None;
+            self.downloads.insert(item.id, item);
+        }
+        Ok(())
+    }
+
+    /// Get the download history file path
+    fn history_path() -> Result<PathBuf, YtdlError> {
+        Ok(Settings::config_dir()?.join("downloads.json"))
+    }
+
+    /// Save the queue and history if persistence is enabled
+    ///
+    /// Progress updates are not persisted; only status and metadata changes are.
+    fn persist(&self) {
+        if let Some(path) = &self.history_path {
+            if let Err(e) = self.save_history(path) {
+                log::warn!("Could not save download history: {}", e);
+            }
+        }
     }
 }
 
+/// Run a single download with yt-dlp, reporting the title back as soon as it is known
+///
+/// `partials` starts with the files of an earlier, paused run and collects the
+/// files written by this one.
+async fn run_download(
+    manager: &Weak<RwLock<DownloadManager>>,
+    id: Uuid,
+    url: &str,
+    settings: &Settings,
+    options: &DownloadOptions,
+    stop: &mut oneshot::Receiver<StopMode>,
+    partials: &mut Vec<PathBuf>,
+) -> Result<CompletedDownload, YtdlError> {
+    let mut ytdlp = YtDlp::from_settings(settings);
+    ytdlp.initialize().await?;
+
+    // Fetched as it will be downloaded, so it also tells where the file will go
+    let metadata = match ytdlp.get_download_metadata(url, &settings.download_path, options).await {
+        Ok(metadata) => {
+            if let Some(thumbnail_url) = metadata.thumbnail.clone() {
+                let video_id = crate::utils::url_validator::extract_video_id(url)
+                    .or_else(|| metadata.id.clone());
+                if let Some(video_id) = video_id {
+                    spawn_thumbnail_fetch(manager.clone(), id, video_id, thumbnail_url, settings);
+                }
+            }
+            if let Some(manager) = manager.upgrade() {
+                manager.write().await.update_download_title(id, metadata.title.clone());
+            }
+            Some(metadata)
+        }
+        Err(e) => {
+            log::warn!("Could not fetch metadata for {}: {}", url, e);
+            None
+        }
+    };
+
+    let mut attempt = 1;
+    loop {
+        let started_at = Utc::now();
+        let result = run_attempt(
+            &ytdlp,
+            manager,
+            id,
+            url,
+            settings,
+            options,
+            metadata.as_ref(),
+            stop,
+            partials,
+        )
+        .await;
+
+        // Only failures yt-dlp reported are classified; cancel and pause are not
+        let failure = result.as_ref().err().and_then(YtdlError::failure_kind);
+
+        if let Some(manager) = manager.upgrade() {
+            manager.write().await.record_attempt(id, DownloadAttempt {
+                started_at,
+                finished_at: Utc::now(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+                failure,
+            });
+        }
+
+        let error = match result {
+            Ok(completed) => {
+                log::info!("Download completed for: {}", url);
+                return Ok(completed);
+            }
+            Err(e) => e,
+        };
+
+        let policy = match failure {
+            Some(kind) => settings.retry.policy_for(kind),
+            None => return Err(error),
+        };
+        if attempt >= policy.max_attempts {
+            return Err(error);
+        }
+
+        let delay = policy.delay_for(attempt);
+        log::warn!(
+            "Attempt {} for {} failed ({}), retrying in {:?}",
+            attempt,
+            url,
+            failure.map(|kind| kind.description()).unwrap_or_default(),
+            delay
+        );
+
+        // Wait out the backoff unless the download is cancelled or paused meanwhile
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            mode = &mut *stop => {
+                if let Ok(StopMode::Pause) = mode {
+                    return Err(YtdlError::Paused);
+                }
+                for partial in partials.iter() {
+                    crate::utils::file_utils::remove_partial_files(partial);
+                }
+                return Err(YtdlError::Cancelled);
+            }
+        }
+        attempt += 1;
+    }
+}
+
+/// Run yt-dlp once, feeding its progress into the manager
+///
+/// Files the attempt writes are added to `partials`.
+#[allow(clippy::too_many_arguments)]
+async fn run_attempt(
+    ytdlp: &YtDlp,
+    manager: &Weak<RwLock<DownloadManager>>,
+    id: Uuid,
+    url: &str,
+    settings: &Settings,
+    options: &DownloadOptions,
+    metadata: Option<&VideoMetadata>,
+    stop: &mut oneshot::Receiver<StopMode>,
+    partials: &mut Vec<PathBuf>,
+) -> Result<CompletedDownload, YtdlError> {
+    // Feed progress updates into the manager while the download runs
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let progress_manager = manager.clone();
+    let forwarder = tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            match progress_manager.upgrade() {
+                Some(manager) => manager.write().await.update_download_progress(id, progress),
+                None => break,
+            }
+        }
+    });
+
+    let result = ytdlp
+        .download_video_with_progress(
+            url,
+            &settings.download_path,
+            options,
+            metadata,
+            progress_tx,
+            stop,
+            partials,
+        )
+        .await;
+
+    // Make sure the last progress update lands before the final status
+    let _ = forwarder.await;
+    result
+}
+
+/// Fetch a thumbnail into the cache in the background and attach it to the download
+fn spawn_thumbnail_fetch(
+    manager: Weak<RwLock<DownloadManager>>,
+    id: Uuid,
+    video_id: String,
+    thumbnail_url: String,
+    settings: &Settings,
+) {
+    let cache = match ThumbnailCache::with_default_dir(settings.thumbnail_cache_size) {
+        Ok(cache) => cache,
+        Err(e) => {
+            log::warn!("Thumbnail cache unavailable: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        match cache.fetch(&video_id, &thumbnail_url).await {
+            Ok(path) => {
+                if let Some(manager) = manager.upgrade() {
+                    manager.write().await.update_download_thumbnail(id, path);
+                }
+            }
+            Err(e) => log::warn!("Could not fetch thumbnail for {}: {}", video_id, e),
+        }
+    });
+}
+
 impl Default for DownloadManager {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_utils::TempDir;
+
+    #[test]
+    fn test_history_round_trip_pauses_running_downloads() {
+        let temp = TempDir::new();
+        let path = temp.path().join("downloads.json");
+
+        let mut manager = DownloadManager::new();
+        let mut running = DownloadItem::new("https://youtu.be/dQw4w9WgXcQ".to_string());
+        running.status = DownloadStatus::Downloading;
+        let mut failed = DownloadItem::new("https://youtu.be/9bZkp7q19f0".to_string());
+        failed.status = DownloadStatus::Failed("HTTP Error 403".to_string());
+        let (running_id, failed_id) = (running.id, failed.id);
+        manager.downloads.insert(running.id, running);
+        manager.downloads.insert(failed.id, failed);
+        manager.save_history(&path).unwrap();
+
+        let mut restored = DownloadManager::new();
+        restored.load_history(&path).unwrap();
+
+        assert_eq!(restored.get_downloads().len(), 2);
+        assert_eq!(restored.get_groups().len(), 0);
+        assert_eq!(restored.downloads[&running_id].status, DownloadStatus::Paused);
+        assert_eq!(
+            restored.downloads[&failed_id].status,
+            DownloadStatus::Failed("HTTP Error 403".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_held_queue_does_not_start_downloads() {
+        let mut manager = DownloadManager::new();
+        manager.hold_queue();
+
+        let id = manager.add_download("https://youtu.be/dQw4w9WgXcQ".to_string()).await.unwrap();
+        manager.start_selected_downloads([Uuid::new_v4()]);
+
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Pending);
+        assert_eq!(manager.active_downloads, 0);
+    }
+
+    #[test]
+    fn test_cancel_paused_download_removes_partial_files() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        std::fs::write(dir.join("Video.mp4.part"), b"").unwrap();
+        std::fs::write(dir.join("Other.mp4.part"), b"").unwrap();
+
+        let mut manager = DownloadManager::new();
+        let mut paused = DownloadItem::new("https://youtu.be/dQw4w9WgXcQ".to_string());
+        paused.status = DownloadStatus::Paused;
+        paused.partial_files = vec![dir.join("Video.mp4")];
+        let id = paused.id;
+        manager.downloads.insert(id, paused);
+
+        manager.cancel_download(id).unwrap();
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Cancelled);
+        assert!(manager.downloads[&id].partial_files.is_empty());
+        assert!(!dir.join("Video.mp4.part").exists());
+        assert!(dir.join("Other.mp4.part").exists());
+    }
+
+    #[test]
+    fn test_resume_before_paused_run_reports_back() {
+        let mut manager = DownloadManager::new();
+        // No free slots, so the test never spawns yt-dlp
+        manager.max_concurrent = 0;
+        let item = DownloadItem::new("https://youtu.be/dQw4w9WgXcQ".to_string());
+        let id = item.id;
+        manager.downloads.insert(id, item);
+
+        let (old_run, _old_stop) = manager.begin_run(id).unwrap();
+        manager.pause_download(id).unwrap();
+        manager.resume_download(id).unwrap();
+        assert_eq!(manager.next_pending(), None);
+
+        let partial = PathBuf::from("/videos/Video.mp4");
+        manager.finish_download(id, old_run, Err(YtdlError::Paused), vec![partial.clone()]);
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Pending);
+        assert_eq!(manager.downloads[&id].partial_files, [partial]);
+        assert_eq!(manager.active_downloads, 0);
+        assert_eq!(manager.next_pending(), Some(id));
+
+        // A late result from the old run leaves the new one alone
+        let (new_run, _new_stop) = manager.begin_run(id).unwrap();
+        manager.finish_download(id, old_run, Err(YtdlError::Paused), Vec::new());
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Downloading);
+        assert!(manager.runs[&id].stop.is_some());
+        assert_eq!(manager.active_downloads, 1);
+
+        manager.finish_download(id, new_run, Ok(CompletedDownload::default()), Vec::new());
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Success);
+        assert!(manager.downloads[&id].partial_files.is_empty());
+        assert_eq!(manager.active_downloads, 0);
+    }
+
+    #[test]
+    fn test_retry_before_cancelled_run_reports_back() {
+        let mut manager = DownloadManager::new();
+        // No free slots, so the test never spawns yt-dlp
+        manager.max_concurrent = 0;
+        let item = DownloadItem::new("https://youtu.be/dQw4w9WgXcQ".to_string());
+        let id = item.id;
+        manager.downloads.insert(id, item);
+
+        let (cancelled_run, _cancelled_stop) = manager.begin_run(id).unwrap();
+        manager.cancel_download(id).unwrap();
+        manager.retry_download(id).unwrap();
+        assert_eq!(manager.next_pending(), None);
+
+        manager.finish_download(id, cancelled_run, Err(YtdlError::Cancelled), Vec::new());
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Pending);
+        assert_eq!(manager.next_pending(), Some(id));
+
+        let (retried_run, _retried_stop) = manager.begin_run(id).unwrap();
+        manager.finish_download(id, cancelled_run, Err(YtdlError::Cancelled), Vec::new());
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Downloading);
+        assert!(manager.runs[&id].stop.is_some());
+
+        manager.finish_download(id, retried_run, Ok(CompletedDownload::default()), Vec::new());
+        assert_eq!(manager.status_of(id).unwrap(), DownloadStatus::Success);
+        assert_eq!(manager.active_downloads, 0);
+    }
+
+    #[test]
+    fn test_group_summaries() {
+        let mut manager = DownloadManager::new();
+        // No free slots, so the test never spawns yt-dlp
+        manager.max_concurrent = 0;
+        let entries = (1..=4)
+            .map(|index| PlaylistEntry {
+                index,
+                id: format!("video{}", index),
+                title: None,
+                url: format!("https://youtu.be/video{}", index),
+                duration: None,
+            })
+            .collect();
+        let group_id = manager
+            .add_group("https://youtube.com/playlist?list=PL1".to_string(), None, entries, None)
+            .unwrap();
+
+        let ids: Vec<_> = manager.get_downloads().iter().map(|d| d.id).collect();
+        manager.downloads.get_mut(&ids[0]).unwrap().status = DownloadStatus::Success;
+        manager.downloads.get_mut(&ids[0]).unwrap().progress = 1.0;
+        manager.downloads.get_mut(&ids[1]).unwrap().status = DownloadStatus::Failed("gone".to_string());
+        manager.downloads.get_mut(&ids[2]).unwrap().status = DownloadStatus::Downloading;
+        manager.downloads.get_mut(&ids[2]).unwrap().progress = 0.5;
+
+        let summaries = manager.get_group_summaries();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].group.id, group_id);
+        assert_eq!(
+            summaries[0].progress,
+            GroupProgress { total: 4, completed: 1, failed: 1, active: 1, progress: 0.375 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events() {
+        let mut manager = DownloadManager::new();
+        manager.hold_queue();
+        let mut events = manager.subscribe();
+
+        let id = manager.add_download("https://youtu.be/dQw4w9WgXcQ".to_string()).await.unwrap();
+        manager.update_download_title(id, "Title".to_string());
+        manager.pause_download(id).unwrap();
+        manager.resume_download(id).unwrap();
+        manager.cancel_download(id).unwrap();
+        manager.retry_download(id).unwrap();
+        manager.remove_download(id);
+
+        let names: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .inspect(|event| assert_eq!(event.id(), id))
+            .map(|event| event.name())
+            .collect();
+        assert_eq!(
+            names,
+            ["queued", "metadata_resolved", "paused", "queued", "cancelled", "queued", "removed"]
+        );
+    }
+}
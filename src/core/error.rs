@@ -0,0 +1,77 @@
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::{DownloadFailure, FailureKind};
+
+/// Errors returned by the core download engine
+#[derive(Debug, Error)]
+pub enum YtdlError {
+    #[error("yt-dlp not available")]
+    ExecutableMissing,
+
+    #[error("Failed to install yt-dlp: {0}")]
+    InstallFailed(String),
+
+    #[error("Thumbnail cache: {0}")]
+    Thumbnail(String),
+
+    #[error("Unrecognized yt-dlp version: {0}")]
+    InvalidVersion(String),
+
+    #[error("yt-dlp {found} is too old, version {minimum} or newer is required")]
+    UnsupportedVersion { found: String, minimum: String },
+
+    #[error("Invalid YouTube URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Failed to run yt-dlp: {0}")]
+    Spawn(#[source] std::io::Error),
+
+    /// yt-dlp exited unsuccessfully outside of a download
+    #[error("{context}: {stderr}")]
+    ProcessFailed { context: &'static str, stderr: String },
+
+    #[error("Failed to parse yt-dlp output: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Could not find config directory")]
+    ConfigDirMissing,
+
+    #[error("Settings I/O error: {0}")]
+    SettingsIo(#[source] std::io::Error),
+
+    #[error("{0}")]
+    InvalidSetting(String),
+
+    /// A download failed; the kind tells why
+    #[error(transparent)]
+    Download(#[from] DownloadFailure),
+
+    #[error("Download cancelled")]
+    Cancelled,
+
+    #[error("Download paused")]
+    Paused,
+
+    #[error("Download not found: {0}")]
+    NotFound(Uuid),
+
+    /// The download is in a state that doesn't allow the operation
+    #[error("{0}")]
+    InvalidState(&'static str),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl YtdlError {
+    /// Classification of a failed download, if this is one
+    pub fn failure_kind(&self) -> Option<FailureKind> {
+        match self {
+            YtdlError::Download(failure) => Some(failure.kind),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T, E = YtdlError> = std::result::Result<T, E>;
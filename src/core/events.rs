@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{CollisionOutcome, DownloadItem, DownloadProgress, DownloadStatus, FailureKind};
+
+/// Number of events a slow subscriber can fall behind before it misses some
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// A change to a download, broadcast by the download manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DownloadEvent {
+    /// Added to the queue, or put back in it by a resume or retry
+    Queued { id: Uuid, item: Box<DownloadItem> },
+    Started { id: Uuid },
+    Progress {
+        id: Uuid,
+        /// Overall progress from 0.0 to 1.0
+        progress: f32,
+        details: DownloadProgress,
+    },
+    /// The title or thumbnail became known
+    MetadataResolved {
+        id: Uuid,
+        title: Option<String>,
+        thumbnail_path: Option<String>,
+    },
+    Completed {
+        id: Uuid,
+        file_path: Option<String>,
+        file_size: Option<u64>,
+        sha256: Option<String>,
+        collision: Option<CollisionOutcome>,
+    },
+    Failed {
+        id: Uuid,
+        error: String,
+        failure: Option<FailureKind>,
+    },
+    Cancelled { id: Uuid },
+    Paused { id: Uuid },
+    Removed { id: Uuid },
+}
+
+impl DownloadEvent {
+    /// The download this event is about
+    pub fn id(&self) -> Uuid {
+        match self {
+            DownloadEvent::Queued { id, .. }
+            | DownloadEvent::Started { id }
+            | DownloadEvent::Progress { id, .. }
+            | DownloadEvent::MetadataResolved { id, .. }
+            | DownloadEvent::Completed { id, .. }
+            | DownloadEvent::Failed { id, .. }
+            | DownloadEvent::Cancelled { id }
+            | DownloadEvent::Paused { id }
+            | DownloadEvent::Removed { id } => *id,
+        }
+    }
+
+    /// Event name, matching the serialized `type`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DownloadEvent::Queued { .. } => "queued",
+            DownloadEvent::Started { .. } => "started",
+            DownloadEvent::Progress { .. } => "progress",
+            DownloadEvent::MetadataResolved { .. } => "metadata_resolved",
+            DownloadEvent::Completed { .. } => "completed",
+            DownloadEvent::Failed { .. } => "failed",
+            DownloadEvent::Cancelled { .. } => "cancelled",
+            DownloadEvent::Paused { .. } => "paused",
+            DownloadEvent::Removed { .. } => "removed",
+        }
+    }
+
+    /// Update a subscriber's copy of the queue with this event
+    pub fn apply(&self, downloads: &mut HashMap<Uuid, DownloadItem>) {
+        if let DownloadEvent::Queued { id, item } = self {
+            downloads.insert(*id, (**item).clone());
+            return;
+        }
+        if let DownloadEvent::Removed { id } = self {
+            downloads.remove(id);
+            return;
+        }
+
+        let Some(download) = downloads.get_mut(&self.id()) else {
+            return;
+        };
+        match self {
+            DownloadEvent::Started { .. } => download.status = DownloadStatus::Downloading,
+            DownloadEvent::Progress { progress, details, .. } => {
+                download.progress = *progress;
+                download.progress_details = Some(details.clone());
+            }
+            DownloadEvent::MetadataResolved { title, thumbnail_path, .. } => {
+                download.title = title.clone();
+                download.thumbnail_path = thumbnail_path.clone();
+            }
+            DownloadEvent::Completed { file_path, file_size, sha256, collision, .. } => {
+                download.status = DownloadStatus::Success;
+                download.progress = 1.0;
+                download.progress_details = None;
+                download.file_path = file_path.clone();
+                download.file_size = *file_size;
+                download.sha256 = sha256.clone();
+                download.collision = *collision;
+            }
+            DownloadEvent::Failed { error, .. } => {
+                download.status = DownloadStatus::Failed(error.clone());
+                download.progress_details = None;
+            }
+            DownloadEvent::Cancelled { .. } => {
+                download.status = DownloadStatus::Cancelled;
+                download.progress_details = None;
+            }
+            DownloadEvent::Paused { .. } => {
+                download.status = DownloadStatus::Paused;
+                download.progress_details = None;
+            }
+            DownloadEvent::Queued { .. } | DownloadEvent::Removed { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_events() {
+        let item = DownloadItem::new("https://youtu.be/dQw4w9WgXcQ".to_string());
+        let id = item.id;
+        let mut downloads = HashMap::new();
+
+        DownloadEvent::Queued { id, item: Box::new(item) }.apply(&mut downloads);
+        DownloadEvent::Started { id }.apply(&mut downloads);
+        DownloadEvent::Progress { id, progress: 0.5, details: DownloadProgress::default() }
+            .apply(&mut downloads);
+        assert_eq!(downloads[&id].status, DownloadStatus::Downloading);
+        assert_eq!(downloads[&id].progress, 0.5);
+
+        DownloadEvent::Completed {
+            id,
+            file_path: Some("/tmp/video.mp4".to_string()),
+            file_size: Some(1024),
+            sha256: None,
+            collision: Some(CollisionOutcome::Skipped),
+        }
+        .apply(&mut downloads);
+        assert_eq!(downloads[&id].status, DownloadStatus::Success);
+        assert_eq!(downloads[&id].progress_details, None);
+        assert_eq!(downloads[&id].collision, Some(CollisionOutcome::Skipped));
+        assert_eq!(downloads[&id].file_size, Some(1024));
+
+        DownloadEvent::Removed { id }.apply(&mut downloads);
+        assert!(downloads.is_empty());
+    }
+
+    #[test]
+    fn test_serialized_type_matches_name() {
+        let event = DownloadEvent::MetadataResolved {
+            id: Uuid::new_v4(),
+            title: Some("Title".to_string()),
+            thumbnail_path: None,
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["type"], event.name());
+    }
+}
@@ -0,0 +1,111 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use super::{Settings, YtdlError};
+use crate::utils::file_utils::check_writable;
+
+/// Free space a download folder needs to be accepted
+pub const MIN_FREE_SPACE: u64 = 100 * 1024 * 1024;
+
+/// Something that asks the user for a folder, such as the platform's native dialog
+pub trait FolderPicker {
+    /// The chosen folder, or `None` if the user cancelled
+    fn pick_folder(&self) -> impl Future<Output = Result<Option<PathBuf>, YtdlError>>;
+}
+
+/// Ask for a new download folder and store it in `settings`
+///
+/// Returns `None` if the user cancelled; the settings are then unchanged.
+pub async fn choose_download_folder(
+    picker: &impl FolderPicker,
+    settings: &mut Settings,
+) -> Result<Option<PathBuf>, YtdlError> {
+    let Some(folder) = picker.pick_folder().await? else {
+        return Ok(None);
+    };
+    settings.set_download_path(folder.clone())?;
+    Ok(Some(folder))
+}
+
+/// Check that downloads can be saved in `dir`, creating it if needed
+pub fn check_download_folder(dir: &Path) -> Result<(), YtdlError> {
+    check_folder(dir, MIN_FREE_SPACE)
+}
+
+fn check_folder(dir: &Path, min_free_space: u64) -> Result<(), YtdlError> {
+    if dir.as_os_str().is_empty() {
+        return Err(YtdlError::InvalidSetting("Choose a download folder".to_string()));
+    }
+    if dir.exists() && !dir.is_dir() {
+        return Err(YtdlError::InvalidSetting(format!("{} is not a folder", dir.display())));
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| {
+        YtdlError::InvalidSetting(format!("Cannot create {}: {}", dir.display(), e))
+    })?;
+    check_writable(dir).map_err(|e| {
+        YtdlError::InvalidSetting(format!("Cannot write to {}: {}", dir.display(), e))
+    })?;
+
+    // Not every file system reports free space; don't block the folder over it
+    match fs4::available_space(dir) {
+        Ok(free) if free < min_free_space => Err(YtdlError::InvalidSetting(format!(
+            "Only {} free in {}, at least {} is needed",
+            crate::utils::format_bytes(free),
+            dir.display(),
+            crate::utils::format_bytes(min_free_space)
+        ))),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            log::warn!("Could not check free space in {}: {}", dir.display(), e);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_utils::TempDir;
+
+    /// Picker that answers with a fixed folder instead of showing a dialog
+    struct FixedPicker(Option<PathBuf>);
+
+    impl FolderPicker for FixedPicker {
+        async fn pick_folder(&self) -> Result<Option<PathBuf>, YtdlError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_choose_download_folder() {
+        let temp = TempDir::new();
+        let dir = temp.path().join("videos");
+        let mut settings = Settings::default();
+
+        let chosen = choose_download_folder(&FixedPicker(Some(dir.clone())), &mut settings).await;
+        assert_eq!(chosen.unwrap(), Some(dir.clone()));
+        assert_eq!(settings.download_path, dir);
+        assert!(dir.is_dir());
+
+        let cancelled = choose_download_folder(&FixedPicker(None), &mut settings).await;
+        assert_eq!(cancelled.unwrap(), None);
+        assert_eq!(settings.download_path, dir);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_unusable_folders() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        let file = dir.join("file.txt");
+        std::fs::write(&file, b"").unwrap();
+
+        let mut settings = Settings::default();
+        let original = settings.download_path.clone();
+        assert!(choose_download_folder(&FixedPicker(Some(file)), &mut settings).await.is_err());
+        assert_eq!(settings.download_path, original);
+
+        assert!(check_folder(dir, 0).is_ok());
+        assert!(check_folder(dir, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A single downloadable format reported by yt-dlp's `--dump-json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Format {
+    pub id: String,
+    pub ext: String,
+    /// Human readable note such as "1080p" or "medium"
+    pub note: Option<String>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    /// Total bitrate in KBit/s
+    pub bitrate: Option<f64>,
+    pub audio_bitrate: Option<f64>,
+    pub video_bitrate: Option<f64>,
+    pub filesize: Option<u64>,
+    pub filesize_approx: Option<u64>,
+    pub protocol: Option<String>,
+    /// Dynamic range as reported by yt-dlp, e.g. "SDR", "HDR10" or "HLG"
+    pub dynamic_range: Option<String>,
+}
+
+/// Which kinds of streams to keep when filtering formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatKind {
+    All,
+    /// Streams with both audio and video
+    Combined,
+    AudioOnly,
+    VideoOnly,
+}
+
+/// How to order formats, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatSort {
+    Resolution,
+    Bitrate,
+    Filesize,
+}
+
+impl Format {
+    /// Build a format from one entry of the `formats` array
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let as_u64 = |key: &str| {
+            value[key]
+                .as_u64()
+                .or_else(|| value[key].as_f64().map(|v| v as u64))
+        };
+        let as_string = |key: &str| value[key].as_str().map(|s| s.to_string());
+
+        Some(Self {
+            id: value["format_id"].as_str()?.to_string(),
+            ext: value["ext"].as_str().unwrap_or("unknown").to_string(),
+            note: as_string("format_note"),
+            vcodec: as_string("vcodec"),
+            acodec: as_string("acodec"),
+            width: as_u64("width").map(|v| v as u32),
+            height: as_u64("height").map(|v| v as u32),
+            fps: value["fps"].as_f64(),
+            bitrate: value["tbr"].as_f64(),
+            audio_bitrate: value["abr"].as_f64(),
+            video_bitrate: value["vbr"].as_f64(),
+            filesize: as_u64("filesize"),
+            filesize_approx: as_u64("filesize_approx"),
+            protocol: as_string("protocol"),
+            dynamic_range: as_string("dynamic_range"),
+        })
+    }
+
+    /// Parse the `formats` array of a `--dump-json` document
+    pub fn list_from_json(info: &serde_json::Value) -> Vec<Self> {
+        info["formats"]
+            .as_array()
+            .map(|formats| formats.iter().filter_map(Self::from_json).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether the format carries a video stream
+    pub fn has_video(&self) -> bool {
+        is_codec_present(&self.vcodec)
+    }
+
+    /// Whether the format carries an audio stream
+    pub fn has_audio(&self) -> bool {
+        is_codec_present(&self.acodec)
+    }
+
+    /// Whether the video stream uses a high dynamic range
+    pub fn is_hdr(&self) -> bool {
+        self.dynamic_range
+            .as_deref()
+            .is_some_and(|range| !range.eq_ignore_ascii_case("SDR"))
+    }
+
+    /// Resolution as "WIDTHxHEIGHT", or "audio only"
+    pub fn resolution(&self) -> String {
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => format!("{}x{}", width, height),
+            (None, Some(height)) => format!("{}p", height),
+            _ if !self.has_video() => "audio only".to_string(),
+            _ => "unknown".to_string(),
+        }
+    }
+
+    /// Exact file size if known, otherwise yt-dlp's estimate
+    pub fn size(&self) -> Option<u64> {
+        self.filesize.or(self.filesize_approx)
+    }
+
+    /// Whether the format matches the given kind
+    pub fn is_kind(&self, kind: FormatKind) -> bool {
+        match kind {
+            FormatKind::All => self.has_audio() || self.has_video(),
+            FormatKind::Combined => self.has_audio() && self.has_video(),
+            FormatKind::AudioOnly => self.has_audio() && !self.has_video(),
+            FormatKind::VideoOnly => self.has_video() && !self.has_audio(),
+        }
+    }
+}
+
+/// Keep the formats of the given kind, optionally capped at a maximum height
+pub fn filter_formats(formats: &[Format], kind: FormatKind, max_height: Option<u32>) -> Vec<Format> {
+    formats
+        .iter()
+        .filter(|format| format.is_kind(kind))
+        .filter(|format| match (max_height, format.height) {
+            (Some(max), Some(height)) => height <= max,
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Sort formats best first by the given criterion
+///
+/// Ties are broken by bitrate; formats missing the value sort last.
+pub fn sort_formats(formats: &mut [Format], by: FormatSort) {
+    let key = |format: &Format| -> (f64, f64) {
+        let primary = match by {
+            FormatSort::Resolution => format.height.map(|h| h as f64),
+            FormatSort::Bitrate => format.bitrate,
+            FormatSort::Filesize => format.size().map(|s| s as f64),
+        };
+        (primary.unwrap_or(-1.0), format.bitrate.unwrap_or(-1.0))
+    };
+
+    formats.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(Ordering::Equal)
+            .then(b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+    });
+}
+
+/// yt-dlp reports a missing stream as the codec "none"
+fn is_codec_present(codec: &Option<String>) -> bool {
+    codec.as_deref().is_some_and(|codec| codec != "none")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_formats() -> Vec<Format> {
+        Format::list_from_json(&json!({
+            "formats": [
+                {"format_id": "140", "ext": "m4a", "vcodec": "none", "acodec": "mp4a.40.2",
+                 "abr": 129.5, "tbr": 129.5, "filesize": 3_500_000, "protocol": "https"},
+                {"format_id": "137", "ext": "mp4", "vcodec": "avc1.640028", "acodec": "none",
+                 "width": 1920, "height": 1080, "fps": 30, "tbr": 4400.0,
+                 "filesize_approx": 120_000_000.0, "dynamic_range": "SDR"},
+                {"format_id": "18", "ext": "mp4", "vcodec": "avc1.42001E", "acodec": "mp4a.40.2",
+                 "width": 640, "height": 360, "tbr": 600.0},
+                {"format_id": "337", "ext": "webm", "vcodec": "vp09.02.51.10", "acodec": "none",
+                 "width": 3840, "height": 2160, "tbr": 18000.0, "dynamic_range": "HDR10"},
+                {"ext": "mhtml", "format_note": "storyboard"}
+            ]
+        }))
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let formats = sample_formats();
+        assert_eq!(formats.len(), 4);
+
+        let audio = &formats[0];
+        assert!(audio.has_audio() && !audio.has_video());
+        assert_eq!(audio.resolution(), "audio only");
+        assert_eq!(audio.size(), Some(3_500_000));
+
+        let video = &formats[1];
+        assert_eq!(video.resolution(), "1920x1080");
+        assert_eq!(video.size(), Some(120_000_000));
+        assert!(!video.is_hdr());
+        assert!(formats[3].is_hdr());
+    }
+
+    #[test]
+    fn test_filter_and_sort_formats() {
+        let formats = sample_formats();
+
+        let audio: Vec<_> = filter_formats(&formats, FormatKind::AudioOnly, None);
+        assert_eq!(audio.len(), 1);
+        assert_eq!(audio[0].id, "140");
+
+        let combined = filter_formats(&formats, FormatKind::Combined, None);
+        assert_eq!(combined[0].id, "18");
+
+        let mut video = filter_formats(&formats, FormatKind::VideoOnly, Some(1080));
+        sort_formats(&mut video, FormatSort::Resolution);
+        assert_eq!(video.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(), ["137"]);
+
+        let mut all = formats.clone();
+        sort_formats(&mut all, FormatSort::Resolution);
+        assert_eq!(all.first().map(|f| f.id.as_str()), Some("337"));
+        assert_eq!(all.last().map(|f| f.id.as_str()), Some("140"));
+    }
+}
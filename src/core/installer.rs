@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::error::Result;
+use super::{UpdateChannel, YtdlError};
+use crate::utils::file_utils::sha256_hex;
+
+/// Index that lists the latest release of each channel, in GitHub's API format
+pub const DEFAULT_RELEASE_INDEX: &str = "https://api.github.com/repos";
+
+/// Name of the checksum file published next to each release
+const CHECKSUMS_FILE: &str = "SHA2-256SUMS";
+
+/// A published yt-dlp release
+#[derive(Debug, Clone)]
+pub struct Release {
+    /// Release tag, which is also the version yt-dlp reports
+    pub version: String,
+    /// Download URL of each asset by file name
+    assets: HashMap<String, String>,
+}
+
+impl Release {
+    /// Parse a release from the index's JSON
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let version = value["tag_name"].as_str()?.to_string();
+        let assets = value["assets"]
+            .as_array()?
+            .iter()
+            .filter_map(|asset| {
+                Some((
+                    asset["name"].as_str()?.to_string(),
+                    asset["browser_download_url"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+
+        Some(Self { version, assets })
+    }
+
+    fn asset_url(&self, name: &str) -> Result<&str> {
+        self.assets
+            .get(name)
+            .map(|url| url.as_str())
+            .ok_or_else(|| YtdlError::InstallFailed(format!("Release {} has no {}", self.version, name)))
+    }
+}
+
+/// Downloads and maintains ytdl-mini's own copy of the yt-dlp binary
+#[derive(Debug, Clone)]
+pub struct YtDlpInstaller {
+    /// Base URL of the release index, e.g. the GitHub API or a local mirror
+    release_index: String,
+    channel: UpdateChannel,
+    install_dir: PathBuf,
+}
+
+impl YtDlpInstaller {
+    pub fn new(release_index: impl Into<String>, channel: UpdateChannel, install_dir: PathBuf) -> Self {
+        Self {
+            release_index: release_index.into().trim_end_matches('/').to_string(),
+            channel,
+            install_dir,
+        }
+    }
+
+    /// Installer for the app data directory
+    pub fn with_default_dir(release_index: impl Into<String>, channel: UpdateChannel) -> Result<Self> {
+        let data_dir = dirs::data_dir().ok_or(YtdlError::ConfigDirMissing)?;
+        Ok(Self::new(release_index, channel, data_dir.join("ytdl-mini").join("bin")))
+    }
+
+    /// Release asset with a standalone binary for this platform
+    pub fn asset_name() -> &'static str {
+        if cfg!(windows) {
+            "yt-dlp.exe"
+        } else if cfg!(target_os = "macos") {
+            "yt-dlp_macos"
+        } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+            "yt-dlp_linux_aarch64"
+        } else if cfg!(target_os = "linux") {
+            "yt-dlp_linux"
+        } else {
+            // Zipimport build that needs a system Python
+            "yt-dlp"
+        }
+    }
+
+    /// Path of the managed binary, whether or not it is installed yet
+    pub fn installed_path(&self) -> PathBuf {
+        let name = if cfg!(windows) { "yt-dlp.exe" } else { "yt-dlp" };
+        self.install_dir.join(name)
+    }
+
+    /// Whether the managed binary has been installed
+    pub fn is_installed(&self) -> bool {
+        self.installed_path().is_file()
+    }
+
+    /// Look up the latest release of the channel
+    pub async fn latest_release(&self) -> Result<Release> {
+        let url = format!("{}/{}/releases/latest", self.release_index, self.channel.repository());
+        let body = self.fetch(&url).await?;
+        let value: serde_json::Value = serde_json::from_slice(&body)?;
+
+        Release::from_json(&value)
+            .ok_or_else(|| YtdlError::InstallFailed(format!("Unexpected release index response from {}", url)))
+    }
+
+    /// Install the latest release of the channel
+    pub async fn install(&self) -> Result<PathBuf> {
+        let release = self.latest_release().await?;
+        self.install_release(&release).await
+    }
+
+    /// Download a release binary, verify its checksum and install it
+    pub async fn install_release(&self, release: &Release) -> Result<PathBuf> {
+        let asset = Self::asset_name();
+        log::info!(
+            "Installing yt-dlp {} ({}) from {}",
+            release.version,
+            self.channel.as_str(),
+            release.asset_url(asset)?
+        );
+
+        let checksums = self.fetch(release.asset_url(CHECKSUMS_FILE)?).await?;
+        let expected = find_checksum(&String::from_utf8_lossy(&checksums), asset)
+            .ok_or_else(|| YtdlError::InstallFailed(format!("No checksum published for {}", asset)))?;
+
+        let binary = self.fetch(release.asset_url(asset)?).await?;
+        let actual = sha256_hex(binary.as_slice())?;
+        if !actual.eq_ignore_ascii_case(&expected) {
+            return Err(YtdlError::InstallFailed(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                asset, expected, actual
+            )));
+        }
+
+        let path = self.installed_path();
+        crate::utils::file_utils::write_atomic(&path, &binary)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        log::info!("yt-dlp installed to {}", path.display());
+        Ok(path)
+    }
+
+    /// Download a URL of the release index
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let install_error = |e: reqwest::Error| YtdlError::InstallFailed(format!("{}: {}", url, e));
+
+        // The GitHub API rejects requests without a user agent
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("ytdl-mini/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(install_error)?;
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(install_error)?;
+        let bytes = response.bytes().await.map_err(install_error)?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Find the checksum for `asset` in a `sha256sum` style listing
+fn find_checksum(checksums: &str, asset: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+        // sha256sum marks binary mode with a leading '*'
+        let name = name.trim().trim_start_matches('*');
+        (name == asset).then(|| hash.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_utils::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve files over plain HTTP on localhost and return the base URL
+    ///
+    /// `files` gets the base URL so responses can link back to the server.
+    async fn serve(files: impl FnOnce(&str) -> Vec<(String, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let files = files(&base_url);
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let read = socket.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+
+                let response = match files.iter().find(|(name, _)| path == format!("/{}", name)) {
+                    Some((_, body)) => {
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                    None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+                };
+                let _ = socket.write_all(&response).await;
+            }
+        });
+
+        base_url
+    }
+
+    /// Serve a nightly release containing `binary` with the given checksum listing
+    async fn serve_release(binary: Vec<u8>, checksums: String) -> String {
+        let asset = YtDlpInstaller::asset_name();
+        serve(|base_url| {
+            let index = serde_json::json!({
+                "tag_name": "2024.08.07.232541",
+                "assets": [
+                    { "name": CHECKSUMS_FILE, "browser_download_url": format!("{}/download/{}", base_url, CHECKSUMS_FILE) },
+                    { "name": asset, "browser_download_url": format!("{}/download/{}", base_url, asset) },
+                ],
+            });
+            vec![
+                ("yt-dlp/yt-dlp-nightly-builds/releases/latest".to_string(), index.to_string().into_bytes()),
+                (format!("download/{}", CHECKSUMS_FILE), checksums.into_bytes()),
+                (format!("download/{}", asset), binary),
+            ]
+        })
+        .await
+    }
+
+    #[test]
+    fn test_find_checksum() {
+        let checksums = "aaa  yt-dlp\nbbb *yt-dlp.exe\nccc  yt-dlp_linux\n";
+        assert_eq!(find_checksum(checksums, "yt-dlp.exe").as_deref(), Some("bbb"));
+        assert_eq!(find_checksum(checksums, "yt-dlp_linux").as_deref(), Some("ccc"));
+        assert_eq!(find_checksum(checksums, "yt-dlp_macos"), None);
+    }
+
+    #[tokio::test]
+    async fn test_install_verifies_checksum() {
+        let binary = b"#!/bin/sh\necho 2024.08.07.232541\n".to_vec();
+        let checksums = format!("{}  {}\n", sha256_hex(binary.as_slice()).unwrap(), YtDlpInstaller::asset_name());
+        let base_url = serve_release(binary.clone(), checksums).await;
+
+        let dir = TempDir::new();
+        let installer =
+            YtDlpInstaller::new(format!("{}/", base_url), UpdateChannel::Nightly, dir.path().to_path_buf());
+        assert_eq!(installer.latest_release().await.unwrap().version, "2024.08.07.232541");
+
+        let path = installer.install().await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), binary);
+        assert!(installer.is_installed());
+    }
+
+    #[tokio::test]
+    async fn test_install_rejects_bad_checksum() {
+        let checksums = format!("{}  {}\n", "0".repeat(64), YtDlpInstaller::asset_name());
+        let base_url = serve_release(b"tampered".to_vec(), checksums).await;
+
+        let dir = TempDir::new();
+        let installer = YtDlpInstaller::new(base_url, UpdateChannel::Nightly, dir.path().to_path_buf());
+        assert!(matches!(installer.install().await, Err(YtdlError::InstallFailed(_))));
+        assert!(!installer.is_installed());
+    }
+}
@@ -1,9 +1,41 @@
 pub mod app_state;
+pub mod collision;
+pub mod command;
 pub mod download_manager;
+pub mod error;
+pub mod events;
+pub mod folder;
+pub mod format;
+pub mod installer;
+pub mod options;
+pub mod playlist;
+pub mod retry;
 pub mod settings;
+pub mod template;
+pub mod thumbnails;
+pub mod version;
 pub mod ytdlp;
 
 pub use app_state::AppState;
-pub use download_manager::{DownloadManager, DownloadItem, DownloadStatus};
-pub use settings::Settings;
-pub use ytdlp::YtDlp;
+pub use collision::{CollisionOutcome, CollisionPolicy};
+pub use command::YtDlpCommand;
+pub use error::YtdlError;
+pub use events::DownloadEvent;
+pub use folder::{choose_download_folder, FolderPicker};
+pub use download_manager::{
+    DownloadGroup, DownloadItem, DownloadManager, DownloadStatus, GroupProgress, GroupSummary,
+};
+pub use format::{Format, FormatKind, FormatSort};
+pub use installer::{Release, YtDlpInstaller};
+pub use options::{
+    AudioFormat, DownloadMode, DownloadOptions, SubtitleFormat, SubtitleOptions, ThumbnailFormat,
+};
+pub use playlist::{Playlist, PlaylistEntry, PlaylistSelection};
+pub use retry::{DownloadFailure, FailureKind, RetryPolicy, RetrySettings};
+pub use settings::{ApiSettings, Settings};
+pub use template::OutputTemplate;
+pub use thumbnails::ThumbnailCache;
+pub use version::{UpdateChannel, YtDlpVersion};
+pub use ytdlp::{
+    Chapter, CompletedDownload, DownloadProgress, StopMode, UpdateCheck, VideoMetadata, YtDlp,
+};
@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::{CollisionPolicy, OutputTemplate};
+
+/// Target formats for audio extraction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Mp3,
+    M4a,
+    Opus,
+    Flac,
+    Wav,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 5] = [
+        AudioFormat::Mp3,
+        AudioFormat::M4a,
+        AudioFormat::Opus,
+        AudioFormat::Flac,
+        AudioFormat::Wav,
+    ];
+
+    /// Name as understood by yt-dlp's `--audio-format`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+}
+
+/// What to fetch for a download: video up to a resolution, or extracted audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DownloadMode {
+    Video {
+        /// Maximum resolution, e.g. "1920x1080"
+        resolution: String,
+    },
+    Audio {
+        format: AudioFormat,
+        /// VBR quality from 0 (best) to 10, or a bitrate such as "192K"
+        quality: String,
+    },
+}
+
+impl DownloadMode {
+    /// yt-dlp arguments selecting the format and post-processing for this mode
+    pub fn to_args(&self) -> Vec<String> {
+        match self {
+            DownloadMode::Video { resolution } => {
+                // Build format selector for the desired resolution
+                let format_selector = format!("best[height<={}]",
+                    resolution.split('x').nth(1).unwrap_or("1080"));
+                vec![
+                    "--format".to_string(), format_selector,
+                    "--merge-output-format".to_string(), "mp4".to_string(),
+                ]
+            }
+            DownloadMode::Audio { format, quality } => vec![
+                "--format".to_string(), "bestaudio/best".to_string(),
+                "--extract-audio".to_string(),
+                "--audio-format".to_string(), format.as_str().to_string(),
+                "--audio-quality".to_string(), quality.clone(),
+            ],
+        }
+    }
+
+    /// Extensions the finished file can have, given the file yt-dlp plans to write
+    ///
+    /// Video keeps the planned extension unless formats are merged into mp4;
+    /// audio is always converted to its format.
+    pub fn output_extensions(&self, planned: &Path) -> Vec<String> {
+        match self {
+            DownloadMode::Video { .. } => {
+                let mut extensions = vec!["mp4".to_string()];
+                if let Some(extension) = planned.extension() {
+                    extensions.push(extension.to_string_lossy().to_lowercase());
+                }
+                extensions
+            }
+            DownloadMode::Audio { format, .. } => vec![format.as_str().to_string()],
+        }
+    }
+}
+
+/// Subtitle file formats yt-dlp can convert to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+}
+
+impl SubtitleFormat {
+    pub const ALL: [SubtitleFormat; 3] = [SubtitleFormat::Srt, SubtitleFormat::Vtt, SubtitleFormat::Ass];
+
+    /// Name as understood by yt-dlp's `--convert-subs`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Ass => "ass",
+        }
+    }
+}
+
+/// Image formats thumbnails can be converted to before embedding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    Jpg,
+    Png,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    /// Name as understood by yt-dlp's `--convert-thumbnails`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpg => "jpg",
+            ThumbnailFormat::Png => "png",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+}
+
+/// Which subtitles to fetch and where to put them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleOptions {
+    pub enabled: bool,
+    /// Language codes or yt-dlp patterns, e.g. "en", "de" or "en.*"
+    pub languages: Vec<String>,
+    /// Also fetch auto-generated captions when no uploaded subtitles exist
+    pub include_auto: bool,
+    pub format: SubtitleFormat,
+    /// Embed subtitles into the video container instead of writing sidecar files
+    pub embed: bool,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: vec!["en".to_string()],
+            include_auto: false,
+            format: SubtitleFormat::Srt,
+            embed: false,
+        }
+    }
+}
+
+impl SubtitleOptions {
+    /// yt-dlp arguments for these subtitle options
+    ///
+    /// Embedding only applies to video downloads; audio keeps sidecar files.
+    pub fn to_args(&self, mode: &DownloadMode) -> Vec<String> {
+        if !self.enabled || self.languages.is_empty() {
+            return Vec::new();
+        }
+
+        let mut args = vec![
+            "--write-subs".to_string(),
+            "--sub-langs".to_string(), self.languages.join(","),
+            "--sub-format".to_string(), format!("{}/best", self.format.as_str()),
+            "--convert-subs".to_string(), self.format.as_str().to_string(),
+        ];
+        if self.include_auto {
+            args.push("--write-auto-subs".to_string());
+        }
+        if self.embed && matches!(mode, DownloadMode::Video { .. }) {
+            args.push("--embed-subs".to_string());
+        }
+        args
+    }
+}
+
+/// Everything that shapes a single yt-dlp download
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadOptions {
+    pub mode: DownloadMode,
+    pub subtitles: SubtitleOptions,
+    /// Embed the thumbnail as cover art
+    pub embed_thumbnail: bool,
+    /// Convert the thumbnail before embedding; `None` keeps the original format
+    pub thumbnail_format: Option<ThumbnailFormat>,
+    /// Where in the download folder files are saved, and under what name
+    pub output_template: OutputTemplate,
+    /// What to do when the file already exists
+    pub collision_policy: CollisionPolicy,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            mode: DownloadMode::Video { resolution: "1920x1080".to_string() },
+            subtitles: SubtitleOptions::default(),
+            embed_thumbnail: false,
+            thumbnail_format: None,
+            output_template: OutputTemplate::default(),
+            collision_policy: CollisionPolicy::default(),
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// yt-dlp arguments for format selection and post-processing
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = self.mode.to_args();
+        args.extend(self.subtitles.to_args(&self.mode));
+        args.extend(self.thumbnail_args());
+        args
+    }
+
+    /// Arguments for embedding the thumbnail as cover art
+    ///
+    /// WAV has no cover art support, so nothing is embedded there.
+    fn thumbnail_args(&self) -> Vec<String> {
+        let supports_cover = !matches!(
+            self.mode,
+            DownloadMode::Audio { format: AudioFormat::Wav, .. }
+        );
+        if !self.embed_thumbnail || !supports_cover {
+            return Vec::new();
+        }
+
+        let mut args = vec!["--embed-thumbnail".to_string()];
+        if let Some(format) = self.thumbnail_format {
+            args.push("--convert-thumbnails".to_string());
+            args.push(format.as_str().to_string());
+        }
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_mode_args() {
+        let video = DownloadMode::Video { resolution: "1280x720".to_string() };
+        assert_eq!(
+            video.to_args(),
+            ["--format", "best[height<=720]", "--merge-output-format", "mp4"]
+        );
+
+        let audio = DownloadMode::Audio { format: AudioFormat::Opus, quality: "5".to_string() };
+        assert_eq!(
+            audio.to_args(),
+            [
+                "--format", "bestaudio/best", "--extract-audio",
+                "--audio-format", "opus", "--audio-quality", "5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subtitle_args() {
+        let video = DownloadMode::Video { resolution: "1920x1080".to_string() };
+        let audio = DownloadMode::Audio { format: AudioFormat::Mp3, quality: "0".to_string() };
+
+        assert!(SubtitleOptions::default().to_args(&video).is_empty());
+
+        let subtitles = SubtitleOptions {
+            enabled: true,
+            languages: vec!["en".to_string(), "de".to_string()],
+            include_auto: true,
+            format: SubtitleFormat::Vtt,
+            embed: true,
+        };
+        assert_eq!(
+            subtitles.to_args(&video),
+            [
+                "--write-subs", "--sub-langs", "en,de", "--sub-format", "vtt/best",
+                "--convert-subs", "vtt", "--write-auto-subs", "--embed-subs",
+            ]
+        );
+        assert!(!subtitles.to_args(&audio).contains(&"--embed-subs".to_string()));
+    }
+
+    #[test]
+    fn test_thumbnail_args() {
+        let mut options = DownloadOptions {
+            embed_thumbnail: true,
+            thumbnail_format: Some(ThumbnailFormat::Jpg),
+            ..Default::default()
+        };
+        assert!(options.to_args().ends_with(&[
+            "--embed-thumbnail".to_string(),
+            "--convert-thumbnails".to_string(),
+            "jpg".to_string(),
+        ]));
+
+        options.mode = DownloadMode::Audio { format: AudioFormat::Wav, quality: "0".to_string() };
+        assert!(!options.to_args().contains(&"--embed-thumbnail".to_string()));
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use super::YtdlError;
+
+/// A playlist, channel or mix expanded with `--flat-playlist`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// One video listed in a playlist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// 1-based position within the playlist
+    pub index: usize,
+    pub id: String,
+    pub title: Option<String>,
+    pub url: String,
+    pub duration: Option<f64>,
+}
+
+/// Which playlist entries to enqueue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaylistSelection {
+    All,
+    /// Inclusive, 1-based index ranges
+    Items(Vec<(usize, usize)>),
+}
+
+impl Playlist {
+    /// Parse the JSON lines printed by `--flat-playlist --dump-json`
+    pub fn from_json_lines(output: &str) -> Result<Self, serde_json::Error> {
+        let mut title = None;
+        let mut entries = Vec::new();
+
+        for line in output.lines().filter(|line| !line.trim().is_empty()) {
+            let value: serde_json::Value = serde_json::from_str(line)?;
+
+            if title.is_none() {
+                title = value["playlist_title"]
+                    .as_str()
+                    .or_else(|| value["playlist"].as_str())
+                    .map(|s| s.to_string());
+            }
+
+            let Some(id) = value["id"].as_str() else {
+                continue;
+            };
+            let index = value["playlist_index"]
+                .as_u64()
+                .map(|i| i as usize)
+                .unwrap_or(entries.len() + 1);
+
+            entries.push(PlaylistEntry {
+                index,
+                id: id.to_string(),
+                title: value["title"].as_str().map(|s| s.to_string()),
+                url: value["url"]
+                    .as_str()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("https://www.youtube.com/watch?v={}", id)),
+                duration: value["duration"].as_f64(),
+            });
+        }
+
+        Ok(Self { title, entries })
+    }
+
+    /// Entries picked by `selection`, in playlist order
+    pub fn select(&self, selection: &PlaylistSelection) -> Vec<PlaylistEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| selection.contains(entry.index))
+            .cloned()
+            .collect()
+    }
+}
+
+impl PlaylistSelection {
+    /// Select a single inclusive range of entries
+    pub fn range(start: usize, end: usize) -> Self {
+        Self::Items(vec![(start, end)])
+    }
+
+    /// Select individual entries by index
+    pub fn indices(indices: &[usize]) -> Self {
+        Self::Items(indices.iter().map(|&i| (i, i)).collect())
+    }
+
+    /// Parse a selection like yt-dlp's `--playlist-items`, e.g. "1-5,8,10-12"
+    pub fn parse(spec: &str) -> Result<Self, YtdlError> {
+        let spec = spec.trim();
+        if spec.is_empty() || spec.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+
+        let parse_index = |value: &str| -> Result<usize, YtdlError> {
+            value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&i| i > 0)
+                .ok_or_else(|| {
+                    YtdlError::InvalidSetting(format!("Invalid playlist index '{}'", value.trim()))
+                })
+        };
+
+        let mut items = Vec::new();
+        for part in spec.split(',') {
+            let (start, end) = match part.split_once('-') {
+                Some((start, end)) => (parse_index(start)?, parse_index(end)?),
+                None => {
+                    let index = parse_index(part)?;
+                    (index, index)
+                }
+            };
+
+            if start > end {
+                return Err(YtdlError::InvalidSetting(format!(
+                    "Invalid playlist range '{}'",
+                    part.trim()
+                )));
+            }
+            items.push((start, end));
+        }
+
+        Ok(Self::Items(items))
+    }
+
+    /// Whether the entry at `index` is selected
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            Self::All => true,
+            Self::Items(items) => items.iter().any(|&(start, end)| (start..=end).contains(&index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flat_playlist() {
+        let output = r#"{"id": "aaa", "title": "Part 1", "url": "https://www.youtube.com/watch?v=aaa", "playlist_title": "Course", "playlist_index": 1, "duration": 120.0}
+{"id": "bbb", "title": "Part 2", "playlist_title": "Course", "playlist_index": 2}
+"#;
+        let playlist = Playlist::from_json_lines(output).unwrap();
+
+        assert_eq!(playlist.title.as_deref(), Some("Course"));
+        assert_eq!(playlist.entries.len(), 2);
+        assert_eq!(playlist.entries[1].url, "https://www.youtube.com/watch?v=bbb");
+        assert_eq!(playlist.entries[0].duration, Some(120.0));
+    }
+
+    #[test]
+    fn test_playlist_selection() {
+        let selection = PlaylistSelection::parse("1-3, 7").unwrap();
+        assert!(selection.contains(2));
+        assert!(selection.contains(7));
+        assert!(!selection.contains(5));
+
+        assert_eq!(PlaylistSelection::parse("all").unwrap(), PlaylistSelection::All);
+        assert_eq!(PlaylistSelection::range(2, 4), PlaylistSelection::Items(vec![(2, 4)]));
+        assert!(PlaylistSelection::indices(&[4, 9]).contains(9));
+        assert!(PlaylistSelection::parse("5-2").is_err());
+        assert!(PlaylistSelection::parse("0").is_err());
+        assert!(PlaylistSelection::parse("x").is_err());
+    }
+}
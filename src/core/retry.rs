@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Why a yt-dlp download failed, classified from its error output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Connection problems, timeouts and server errors
+    Network,
+    /// HTTP 403 Forbidden
+    Forbidden,
+    /// HTTP 429 Too Many Requests
+    RateLimited,
+    GeoBlocked,
+    /// Private, removed or otherwise unavailable video
+    Unavailable,
+    AgeRestricted,
+    MembersOnly,
+    FormatUnavailable,
+    DiskFull,
+    Other,
+}
+
+/// Patterns in yt-dlp's stderr for each failure kind, checked in order
+const FAILURE_PATTERNS: &[(FailureKind, &[&str])] = &[
+    (FailureKind::DiskFull, &["no space left on device", "errno 28", "disk full"]),
+    (FailureKind::RateLimited, &["http error 429", "too many requests"]),
+    (FailureKind::Forbidden, &["http error 403", "403: forbidden"]),
+    (FailureKind::MembersOnly, &["members-only", "join this channel to get access", "available to this channel's members"]),
+    (FailureKind::AgeRestricted, &["confirm your age", "age-restricted", "age restricted", "inappropriate for some users"]),
+    (FailureKind::GeoBlocked, &["available in your country", "geo restrict", "geo-restrict", "blocked it in your country"]),
+    (FailureKind::Unavailable, &["private video", "video unavailable", "has been removed", "been terminated", "this video is private", "no longer available"]),
+    (FailureKind::FormatUnavailable, &["requested format is not available", "no video formats found"]),
+    (FailureKind::Network, &[
+        "unable to download webpage", "connection reset", "connection refused", "connection aborted",
+        "timed out", "temporary failure in name resolution", "network is unreachable",
+        "urlopen error", "incompleteread", "http error 5", "getaddrinfo failed",
+    ]),
+];
+
+impl FailureKind {
+    /// Classify yt-dlp error output
+    pub fn classify(stderr: &str) -> Self {
+        let stderr = stderr.to_lowercase();
+        FAILURE_PATTERNS
+            .iter()
+            .find(|(_, patterns)| patterns.iter().any(|pattern| stderr.contains(pattern)))
+            .map(|(kind, _)| *kind)
+            .unwrap_or(FailureKind::Other)
+    }
+
+    /// Whether retrying the same download might succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(self, FailureKind::Network | FailureKind::Forbidden | FailureKind::RateLimited)
+    }
+
+    /// Short user facing description
+    pub fn description(&self) -> &'static str {
+        match self {
+            FailureKind::Network => "Network error",
+            FailureKind::Forbidden => "Access forbidden (HTTP 403)",
+            FailureKind::RateLimited => "Rate limited (HTTP 429)",
+            FailureKind::GeoBlocked => "Not available in your country",
+            FailureKind::Unavailable => "Video is private or removed",
+            FailureKind::AgeRestricted => "Age-restricted video",
+            FailureKind::MembersOnly => "Members-only video",
+            FailureKind::FormatUnavailable => "Requested format not available",
+            FailureKind::DiskFull => "Disk full",
+            FailureKind::Other => "Download failed",
+        }
+    }
+}
+
+/// A failed yt-dlp run together with its classification
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Download failed: {message}")]
+pub struct DownloadFailure {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl DownloadFailure {
+    /// Build a failure from yt-dlp's stderr
+    pub fn from_stderr(stderr: &str) -> Self {
+        Self {
+            kind: FailureKind::classify(stderr),
+            message: stderr.trim().to_string(),
+        }
+    }
+}
+
+/// How often and how fast to retry a class of failures
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with every further retry
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Random spread applied to each delay, as a fraction (0.2 = ±20%)
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        base_delay_ms: 0,
+        max_delay_ms: 0,
+        jitter: 0.0,
+    };
+
+    /// Delay before retrying after the given (1-based) failed attempt
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        // Random value in [-1.0, 1.0) from the OS RNG behind uuid
+        let random = (uuid::Uuid::new_v4().as_u128() % 2001) as f64 / 1000.0 - 1.0;
+        self.delay_with_jitter(attempt, random)
+    }
+
+    /// Exponential backoff with `random` in [-1.0, 1.0] scaling the jitter
+    fn delay_with_jitter(&self, attempt: u32, random: f64) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms);
+        let jitter = self.jitter.clamp(0.0, 1.0) * random.clamp(-1.0, 1.0);
+
+        Duration::from_millis((backoff as f64 * (1.0 + jitter)).max(0.0) as u64)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 2_000,
+            max_delay_ms: 60_000,
+            jitter: 0.2,
+        }
+    }
+}
+
+/// Retry policies per failure class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    pub policies: HashMap<FailureKind, RetryPolicy>,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        let policies = HashMap::from([
+            (FailureKind::Network, RetryPolicy { max_attempts: 5, ..Default::default() }),
+            (FailureKind::Forbidden, RetryPolicy { max_attempts: 3, base_delay_ms: 5_000, ..Default::default() }),
+            (
+                FailureKind::RateLimited,
+                RetryPolicy {
+                    max_attempts: 4,
+                    base_delay_ms: 30_000,
+                    max_delay_ms: 300_000,
+                    jitter: 0.3,
+                },
+            ),
+        ]);
+        Self { policies }
+    }
+}
+
+impl RetrySettings {
+    /// Policy for a failure class; permanent failures are never retried
+    pub fn policy_for(&self, kind: FailureKind) -> RetryPolicy {
+        if !kind.is_transient() {
+            return RetryPolicy::NONE;
+        }
+        self.policies.get(&kind).copied().unwrap_or(RetryPolicy::NONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failures() {
+        let cases = [
+            ("ERROR: unable to download video data: HTTP Error 403: Forbidden", FailureKind::Forbidden),
+            ("ERROR: HTTP Error 429: Too Many Requests", FailureKind::RateLimited),
+            ("ERROR: [youtube] abc: Video unavailable. The uploader has not made this video available in your country", FailureKind::GeoBlocked),
+            ("ERROR: [youtube] abc: Private video. Sign in if you've been granted access", FailureKind::Unavailable),
+            ("ERROR: [youtube] abc: Sign in to confirm your age. This video may be inappropriate for some users.", FailureKind::AgeRestricted),
+            ("ERROR: [youtube] abc: Join this channel to get access to members-only content", FailureKind::MembersOnly),
+            ("ERROR: [youtube] abc: Requested format is not available", FailureKind::FormatUnavailable),
+            ("ERROR: unable to write data: [Errno 28] No space left on device", FailureKind::DiskFull),
+            ("ERROR: [youtube] abc: Unable to download webpage: <urlopen error [Errno -3] Temporary failure in name resolution>", FailureKind::Network),
+            ("ERROR: something odd", FailureKind::Other),
+        ];
+
+        for (stderr, kind) in cases {
+            assert_eq!(FailureKind::classify(stderr), kind, "{}", stderr);
+        }
+    }
+
+    #[test]
+    fn test_backoff_delays() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay_ms: 1_000,
+            max_delay_ms: 5_000,
+            jitter: 0.5,
+        };
+
+        assert_eq!(policy.delay_with_jitter(1, 0.0), Duration::from_millis(1_000));
+        assert_eq!(policy.delay_with_jitter(3, 0.0), Duration::from_millis(4_000));
+        assert_eq!(policy.delay_with_jitter(10, 0.0), Duration::from_millis(5_000));
+        assert_eq!(policy.delay_with_jitter(2, 1.0), Duration::from_millis(3_000));
+        assert_eq!(policy.delay_with_jitter(2, -1.0), Duration::from_millis(1_000));
+
+        let delay = policy.delay_for(2);
+        assert!(delay >= Duration::from_millis(1_000) && delay <= Duration::from_millis(3_000));
+    }
+
+    #[test]
+    fn test_only_transient_failures_retry() {
+        let settings = RetrySettings::default();
+        assert_eq!(settings.policy_for(FailureKind::Network).max_attempts, 5);
+        assert_eq!(settings.policy_for(FailureKind::Unavailable), RetryPolicy::NONE);
+        assert_eq!(settings.policy_for(FailureKind::DiskFull), RetryPolicy::NONE);
+    }
+}
@@ -1,8 +1,17 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use super::{
+    AudioFormat, CollisionPolicy, DownloadMode, DownloadOptions, OutputTemplate, RetrySettings,
+    SubtitleOptions, ThumbnailFormat, UpdateChannel, YtDlpCommand, YtdlError,
+};
+
+/// Upper limit for `max_concurrent_downloads`
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 16;
+
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     /// Default video resolution for downloads
     pub default_resolution: String,
@@ -10,6 +19,60 @@ pub struct Settings {
     pub download_path: PathBuf,
     /// Maximum concurrent downloads
     pub max_concurrent_downloads: usize,
+    /// Extract audio instead of downloading video by default
+    pub audio_only: bool,
+    /// Target format for audio extraction
+    pub audio_format: AudioFormat,
+    /// Audio quality, 0 (best) to 10 or a bitrate such as "192K"
+    pub audio_quality: String,
+    /// Subtitle and caption downloading
+    pub subtitles: SubtitleOptions,
+    /// yt-dlp template for file names, relative to the download path
+    pub output_template: OutputTemplate,
+    /// What to do when a download's file already exists
+    pub collision_policy: CollisionPolicy,
+    /// Embed the thumbnail as cover art in downloaded files
+    pub embed_thumbnail: bool,
+    /// Convert thumbnails before embedding; `None` keeps the original format
+    pub thumbnail_format: Option<ThumbnailFormat>,
+    /// Size limit of the thumbnail cache in bytes
+    pub thumbnail_cache_size: u64,
+    /// How transient download failures are retried
+    pub retry: RetrySettings,
+    /// Release index the standalone yt-dlp binary is installed and updated from
+    #[serde(alias = "ytdlp_release_url")]
+    pub ytdlp_release_index: String,
+    /// Which yt-dlp builds to install and update to
+    pub update_channel: UpdateChannel,
+    /// Check for a newer yt-dlp when the app starts
+    pub auto_check_updates: bool,
+    /// Run yt-dlp with this command instead of the managed or PATH binary
+    pub ytdlp_command: Option<YtDlpCommand>,
+    /// Extra arguments added to every yt-dlp call
+    pub ytdlp_extra_args: Vec<String>,
+    /// Local HTTP control API
+    pub api: ApiSettings,
+}
+
+/// Settings for the local HTTP control API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    /// Serve the API on 127.0.0.1 while the app runs
+    pub enabled: bool,
+    pub port: u16,
+    /// Bearer token clients must send; generated when the API first starts
+    pub token: String,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9477,
+            token: String::new(),
+        }
+    }
 }
 
 impl Default for Settings {
@@ -18,17 +81,33 @@ impl Default for Settings {
             default_resolution: "1920x1080".to_string(),
             download_path: crate::utils::get_downloads_dir(),
             max_concurrent_downloads: 3,
+            audio_only: false,
+            audio_format: AudioFormat::Mp3,
+            audio_quality: "0".to_string(),
+            subtitles: SubtitleOptions::default(),
+            output_template: OutputTemplate::default(),
+            collision_policy: CollisionPolicy::default(),
+            embed_thumbnail: false,
+            thumbnail_format: Some(ThumbnailFormat::Jpg),
+            thumbnail_cache_size: crate::core::thumbnails::DEFAULT_CACHE_SIZE,
+            retry: RetrySettings::default(),
+            ytdlp_release_index: crate::core::installer::DEFAULT_RELEASE_INDEX.to_string(),
+            update_channel: UpdateChannel::default(),
+            auto_check_updates: true,
+            ytdlp_command: None,
+            ytdlp_extra_args: Vec::new(),
+            api: ApiSettings::default(),
         }
     }
 }
 
 impl Settings {
     /// Load settings from file
-    pub fn load() -> Result<Self, anyhow::Error> {
+    pub fn load() -> Result<Self, YtdlError> {
         let config_path = Self::config_path()?;
         
         if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)?;
+            let content = std::fs::read_to_string(&config_path).map_err(YtdlError::SettingsIo)?;
             let settings: Settings = serde_json::from_str(&content)?;
             Ok(settings)
         } else {
@@ -37,45 +116,247 @@ impl Settings {
     }
 
     /// Save settings to file
-    pub fn save(&self) -> Result<(), anyhow::Error> {
+    pub fn save(&self) -> Result<(), YtdlError> {
         let config_path = Self::config_path()?;
         
         if let Some(parent) = config_path.parent() {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).map_err(YtdlError::SettingsIo)?;
         }
         
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&config_path, content)?;
+        std::fs::write(&config_path, content).map_err(YtdlError::SettingsIo)?;
         
         Ok(())
     }
 
     /// Get the configuration file path
-    fn config_path() -> Result<PathBuf, anyhow::Error> {
+    fn config_path() -> Result<PathBuf, YtdlError> {
+        Ok(Self::config_dir()?.join("config.json"))
+    }
+
+    /// Get the directory holding ytdl-mini's configuration and data files
+    pub(crate) fn config_dir() -> Result<PathBuf, YtdlError> {
         let config_dir = dirs::config_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
+            .ok_or(YtdlError::ConfigDirMissing)?;
         
-        Ok(config_dir.join("ytdl-mini").join("config.json"))
+        Ok(config_dir.join("ytdl-mini"))
     }
 
     /// Validate and update resolution
-    pub fn set_resolution(&mut self, resolution: String) -> Result<(), anyhow::Error> {
+    pub fn set_resolution(&mut self, resolution: String) -> Result<(), YtdlError> {
         // Basic validation for resolution format
         if resolution.contains('x') && resolution.split('x').count() == 2 {
             self.default_resolution = resolution;
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Invalid resolution format. Use format like '1920x1080'"))
+            Err(YtdlError::InvalidSetting(
+                "Invalid resolution format. Use format like '1920x1080'".to_string(),
+            ))
         }
     }
 
-    /// Set download path
-    pub fn set_download_path(&mut self, path: PathBuf) -> Result<(), anyhow::Error> {
-        if path.exists() || std::fs::create_dir_all(&path).is_ok() {
-            self.download_path = path;
+    /// Validate and update audio quality
+    pub fn set_audio_quality(&mut self, quality: String) -> Result<(), YtdlError> {
+        let quality = quality.trim().to_string();
+        let is_vbr = quality.parse::<u8>().is_ok_and(|q| q <= 10);
+        let is_bitrate = quality
+            .strip_suffix(['K', 'k'])
+            .is_some_and(|rate| !rate.is_empty() && rate.chars().all(|c| c.is_ascii_digit()));
+
+        if is_vbr || is_bitrate {
+            self.audio_quality = quality;
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Cannot create or access download directory"))
+            Err(YtdlError::InvalidSetting(
+                "Invalid audio quality. Use 0 (best) to 10, or a bitrate like '192K'".to_string(),
+            ))
         }
     }
+
+    /// Default download mode derived from these settings
+    pub fn download_mode(&self) -> DownloadMode {
+        if self.audio_only {
+            DownloadMode::Audio {
+                format: self.audio_format,
+                quality: self.audio_quality.clone(),
+            }
+        } else {
+            DownloadMode::Video {
+                resolution: self.default_resolution.clone(),
+            }
+        }
+    }
+
+    /// Validate and update the subtitle languages
+    pub fn set_subtitle_languages(&mut self, languages: Vec<String>) -> Result<(), YtdlError> {
+        let languages: Vec<String> = languages
+            .iter()
+            .map(|language| language.trim().to_string())
+            .filter(|language| !language.is_empty())
+            .collect();
+
+        let is_valid = |language: &String| {
+            language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*'))
+        };
+
+        if languages.is_empty() {
+            Err(YtdlError::InvalidSetting("At least one subtitle language is required".to_string()))
+        } else if let Some(invalid) = languages.iter().find(|l| !is_valid(l)) {
+            Err(YtdlError::InvalidSetting(format!(
+                "Invalid subtitle language '{}'. Use codes like 'en' or 'pt-BR'",
+                invalid
+            )))
+        } else {
+            self.subtitles.languages = languages;
+            Ok(())
+        }
+    }
+
+    /// Options for a download, using `mode` instead of the default mode if given
+    pub fn download_options(&self, mode: Option<DownloadMode>) -> DownloadOptions {
+        DownloadOptions {
+            mode: mode.unwrap_or_else(|| self.download_mode()),
+            subtitles: self.subtitles.clone(),
+            embed_thumbnail: self.embed_thumbnail,
+            thumbnail_format: self.thumbnail_format,
+            output_template: self.output_template.clone(),
+            collision_policy: self.collision_policy,
+        }
+    }
+
+    /// Validate and update the filename template
+    pub fn set_output_template(&mut self, template: &str) -> Result<(), YtdlError> {
+        self.output_template = template.parse()?;
+        Ok(())
+    }
+
+    /// Validate and update the yt-dlp command; an empty command restores auto-detection
+    pub fn set_ytdlp_command(&mut self, command: &str) -> Result<(), YtdlError> {
+        if command.trim().is_empty() {
+            self.ytdlp_command = None;
+            return Ok(());
+        }
+
+        let command: YtDlpCommand = command.parse()?;
+        command.resolve()?;
+        self.ytdlp_command = Some(command);
+        Ok(())
+    }
+
+    /// Validate and update the extra yt-dlp arguments
+    pub fn set_ytdlp_extra_args(&mut self, args: Vec<String>) -> Result<(), YtdlError> {
+        crate::core::command::validate_extra_args(&args)?;
+        self.ytdlp_extra_args = args;
+        Ok(())
+    }
+
+    /// Read a setting by name; nested settings use dots, e.g. `subtitles.enabled`
+    pub fn get_value(&self, key: &str) -> Result<serde_json::Value, YtdlError> {
+        let root = serde_json::to_value(self)?;
+        key.split('.')
+            .try_fold(&root, |value, part| value.get(part))
+            .cloned()
+            .ok_or_else(|| YtdlError::InvalidSetting(format!("Unknown setting: {}", key)))
+    }
+
+    /// Update a setting by name from text, e.g. a command line argument
+    ///
+    /// The text is read as JSON if possible and as a plain string otherwise.
+    /// Settings with their own setter are validated by it.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<(), YtdlError> {
+        match key {
+            "default_resolution" => return self.set_resolution(value.to_string()),
+            "audio_quality" => return self.set_audio_quality(value.to_string()),
+            "download_path" => return self.set_download_path(crate::utils::expand_path(value)),
+            "max_concurrent_downloads" => {
+                let max = value.trim().parse().map_err(|_| {
+                    YtdlError::InvalidSetting(format!("Not a number of downloads: {}", value))
+                })?;
+                return self.set_max_concurrent_downloads(max);
+            }
+            "output_template" => return self.set_output_template(value),
+            "ytdlp_command" => return self.set_ytdlp_command(value),
+            "ytdlp_extra_args" => {
+                return self.set_ytdlp_extra_args(crate::core::command::split_args(value)?)
+            }
+            "subtitles.languages" => {
+                return self.set_subtitle_languages(value.split(',').map(|l| l.to_string()).collect())
+            }
+            _ => {}
+        }
+
+        let mut root = serde_json::to_value(&*self)?;
+        let slot = key
+            .split('.')
+            .try_fold(&mut root, |value, part| value.get_mut(part))
+            .ok_or_else(|| YtdlError::InvalidSetting(format!("Unknown setting: {}", key)))?;
+        *slot = serde_json::from_str(value)
+            .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+
+        *self = serde_json::from_value(root)
+            .map_err(|e| YtdlError::InvalidSetting(format!("Invalid value for {}: {}", key, e)))?;
+        Ok(())
+    }
+
+    /// Validate and update how many downloads run at once
+    pub fn set_max_concurrent_downloads(&mut self, max: usize) -> Result<(), YtdlError> {
+        if (1..=MAX_CONCURRENT_DOWNLOADS).contains(&max) {
+            self.max_concurrent_downloads = max;
+            Ok(())
+        } else {
+            Err(YtdlError::InvalidSetting(format!(
+                "Concurrent downloads must be between 1 and {}",
+                MAX_CONCURRENT_DOWNLOADS
+            )))
+        }
+    }
+
+    /// Set download path
+    ///
+    /// The folder is created if needed and must be writable with enough free space.
+    pub fn set_download_path(&mut self, path: PathBuf) -> Result<(), YtdlError> {
+        super::folder::check_download_folder(&path)?;
+        self.download_path = path;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_and_set_values() {
+        let mut settings = Settings::default();
+
+        settings.set_value("max_concurrent_downloads", "5").unwrap();
+        settings.set_value("subtitles.enabled", "true").unwrap();
+        settings.set_value("update_channel", "nightly").unwrap();
+        settings.set_value("collision_policy", "append_id").unwrap();
+        settings.set_value("ytdlp_extra_args", "--proxy 'socks5://127.0.0.1:1080'").unwrap();
+
+        assert_eq!(settings.get_value("max_concurrent_downloads").unwrap(), 5);
+        assert_eq!(settings.get_value("subtitles.enabled").unwrap(), true);
+        assert_eq!(settings.update_channel, UpdateChannel::Nightly);
+        assert_eq!(settings.collision_policy, CollisionPolicy::AppendId);
+        assert_eq!(settings.ytdlp_extra_args, ["--proxy", "socks5://127.0.0.1:1080"]);
+
+        assert!(settings.set_value("default_resolution", "big").is_err());
+        assert!(settings.set_value("ytdlp_extra_args", "--output x").is_err());
+        assert!(settings.set_value("max_concurrent_downloads", "many").is_err());
+        assert!(settings.set_value("max_concurrent_downloads", "0").is_err());
+        assert!(settings.set_value("output_template", "%(title)s").is_err());
+        assert!(settings.set_value("no_such_setting", "1").is_err());
+        assert!(settings.get_value("subtitles.nope").is_err());
+        assert_eq!(settings.max_concurrent_downloads, 5);
+    }
+
+    #[test]
+    fn test_load_renamed_release_url() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"ytdlp_release_url": "https://mirror.example/repos"}"#).unwrap();
+        assert_eq!(settings.ytdlp_release_index, "https://mirror.example/repos");
+    }
 }
@@ -0,0 +1,449 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::{self, Write};
+use std::path::{Component, Path};
+use std::str::FromStr;
+
+use super::{VideoMetadata, YtdlError};
+
+/// Template used when none is configured
+pub const DEFAULT_TEMPLATE: &str = "%(title)s.%(ext)s";
+
+/// Ready-made templates offered in settings, as (name, template)
+pub const PRESETS: &[(&str, &str)] = &[
+    ("Title", DEFAULT_TEMPLATE),
+    ("Title and id", "%(title)s [%(id)s].%(ext)s"),
+    ("Date and title", "%(upload_date)s - %(title)s.%(ext)s"),
+    ("Uploader folder", "%(uploader)s/%(title)s.%(ext)s"),
+    ("Playlist folder", "%(playlist_title)s/%(playlist_index)03d - %(title)s.%(ext)s"),
+];
+
+/// Printf conversion types, plus yt-dlp's own such as `B` (bytes) and `S` (sanitized)
+const CONVERSIONS: &str = "diouxXeEfFgGcrsaBjhlqDSU";
+
+/// What yt-dlp writes for a field the video doesn't have
+const MISSING_VALUE: &str = "NA";
+
+/// yt-dlp output template for downloaded files, relative to the download folder
+///
+/// Templates use yt-dlp's field syntax, such as `%(title).50s`,
+/// `%(uploader|Unknown)s` or `%(upload_date>%Y)s`, may contain `/` to create
+/// subfolders and must keep the file extension, e.g.
+/// `%(uploader)s/%(upload_date)s - %(title)s.%(ext)s`.
+///
+/// Parsing validates the template. Deserializing doesn't, so a hand-edited
+/// config.json still loads; an invalid template is kept as written and
+/// downloads use the default instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct OutputTemplate(String);
+
+impl OutputTemplate {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The `--output` value for downloads saved under `dir`
+    pub fn output_arg(&self, dir: &Path) -> String {
+        let template = match validate(&self.0) {
+            Ok(()) => self.0.as_str(),
+            Err(e) => {
+                log::warn!("Using the default filename template instead of {:?}: {}", self.0, e);
+                DEFAULT_TEMPLATE
+            }
+        };
+        Path::new(&escape(&dir.to_string_lossy())).join(template).to_string_lossy().into_owned()
+    }
+
+    /// The path, relative to the download folder, this template gives for a video
+    ///
+    /// Mirrors yt-dlp's substitution closely enough for a preview: fields come
+    /// from the video's JSON, missing ones become `NA` and slashes inside values
+    /// are replaced so they can't create folders.
+    pub fn render(&self, metadata: &VideoMetadata) -> String {
+        let mut rendered = String::new();
+        for token in tokenize(&self.0).unwrap_or_default() {
+            match token {
+                Token::Literal(text) => rendered.push_str(text),
+                Token::Field { expr, spec } => {
+                    let value = render_field(metadata, expr, &spec);
+                    rendered.push_str(&value.replace(['/', '\\'], "_"));
+                }
+            }
+        }
+        rendered
+    }
+}
+
+impl Default for OutputTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_TEMPLATE.to_string())
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = YtdlError;
+
+    /// Parse and validate a template
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let template = s.trim();
+        validate(template).map_err(YtdlError::InvalidSetting)?;
+        Ok(Self(template.to_string()))
+    }
+}
+
+impl From<String> for OutputTemplate {
+    fn from(template: String) -> Self {
+        Self(template)
+    }
+}
+
+impl From<OutputTemplate> for String {
+    fn from(template: OutputTemplate) -> Self {
+        template.0
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Make text appear literally in a template
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('%', "%%")
+}
+
+/// Metadata of a made-up video for previewing templates
+pub fn sample_metadata() -> VideoMetadata {
+    VideoMetadata::from_json(json!({
+        "id": "dQw4w9WgXcQ",
+        "title": "Never Gonna Give You Up",
+        "fulltitle": "Never Gonna Give You Up",
+        "ext": "mp4",
+        "uploader": "Rick Astley",
+        "uploader_id": "@RickAstleyYT",
+        "channel": "Rick Astley",
+        "channel_id": "UCuAXFkgsw1L7xaCfnd5JJOw",
+        "upload_date": "20091025",
+        "timestamp": 1256453853,
+        "duration": 213,
+        "duration_string": "3:33",
+        "view_count": 1_500_000_000u64,
+        "like_count": 17_000_000,
+        "playlist": "Favourites",
+        "playlist_title": "Favourites",
+        "playlist_id": "PL0123456789",
+        "playlist_index": 7,
+        "playlist_count": 42,
+        "autonumber": 1,
+        "resolution": "1920x1080",
+        "width": 1920,
+        "height": 1080,
+        "fps": 25,
+        "format_id": "137+140",
+        "extractor": "youtube",
+    }))
+}
+
+/// Why a template can't be used, if it can't
+fn validate(template: &str) -> Result<(), String> {
+    if template.is_empty() {
+        return Err("The filename template is empty".to_string());
+    }
+
+    let path = Path::new(template);
+    if path.has_root() || path.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err("The filename template must stay inside the download folder".to_string());
+    }
+
+    for token in tokenize(template)? {
+        if let Token::Field { expr: "", .. } = token {
+            return Err("A template field has no name".to_string());
+        }
+    }
+
+    let file_name = template.rsplit(['/', '\\']).next().unwrap_or(template);
+    let has_ext = tokenize(file_name)?
+        .iter()
+        .any(|token| matches!(token, Token::Field { expr: "ext", .. }));
+    if !has_ext {
+        return Err("The file name must end with the extension field %(ext)s".to_string());
+    }
+    Ok(())
+}
+
+/// A piece of a template
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Literal(&'a str),
+    /// `%(expr)spec`, e.g. `%(upload_date>%Y|NA)s` or `%(title).50s`
+    Field { expr: &'a str, spec: Spec },
+}
+
+/// A printf-style conversion such as `s`, `03d` or `.50s`
+#[derive(Debug, Default, PartialEq)]
+struct Spec {
+    left_align: bool,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+}
+
+impl Spec {
+    /// Parse the conversion at the start of `text`, returning it and its length
+    fn parse(text: &str) -> Option<(Spec, usize)> {
+        let mut spec = Spec::default();
+        let mut chars = text.char_indices().peekable();
+
+        while let Some(&(_, flag)) = chars.peek().filter(|(_, c)| "#0-+ ".contains(*c)) {
+            spec.left_align |= flag == '-';
+            spec.zero_pad |= flag == '0';
+            chars.next();
+        }
+        let number = |chars: &mut std::iter::Peekable<std::str::CharIndices>| {
+            let mut digits = String::new();
+            while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                digits.push(digit);
+            }
+            digits.parse::<usize>().ok()
+        };
+        spec.width = number(&mut chars).unwrap_or(0);
+        if chars.next_if(|&(_, c)| c == '.').is_some() {
+            spec.precision = Some(number(&mut chars).unwrap_or(0));
+        }
+
+        let (i, conversion) = chars.next().filter(|(_, c)| CONVERSIONS.contains(*c))?;
+        spec.conversion = conversion;
+        Some((spec, i + conversion.len_utf8()))
+    }
+
+    fn is_integer(&self) -> bool {
+        matches!(self.conversion, 'd' | 'i')
+    }
+}
+
+/// Split a template into literal text and fields
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('%') {
+        if start > 0 {
+            tokens.push(Token::Literal(&rest[..start]));
+        }
+        let after = &rest[start + 1..];
+
+        if let Some(after) = after.strip_prefix('%') {
+            tokens.push(Token::Literal("%"));
+            rest = after;
+            continue;
+        }
+        let Some(field) = after.strip_prefix('(') else {
+            return Err("A lone % must be written as %%".to_string());
+        };
+        let close = field
+            .find(')')
+            .ok_or_else(|| "A template field is missing its closing parenthesis".to_string())?;
+        let expr = &field[..close];
+
+        let (spec, spec_len) = Spec::parse(&field[close + 1..])
+            .ok_or_else(|| format!("Field {} needs a type such as %({})s", expr, expr))?;
+
+        tokens.push(Token::Field { expr, spec });
+        rest = &field[close + 1 + spec_len..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Literal(rest));
+    }
+    Ok(tokens)
+}
+
+/// Substitute one field the way yt-dlp would
+///
+/// Handles alternatives (`a,b`), replacements (`a&text {}`), defaults (`a|text`)
+/// and date formatting (`upload_date>%Y`); arithmetic isn't evaluated, so such
+/// fields show as missing.
+fn render_field(metadata: &VideoMetadata, expr: &str, spec: &Spec) -> String {
+    let (expr, default) = match expr.split_once('|') {
+        Some((expr, default)) => (expr, Some(default)),
+        None => (expr, None),
+    };
+    let (expr, replacement) = match expr.split_once('&') {
+        Some((expr, replacement)) => (expr, Some(replacement)),
+        None => (expr, None),
+    };
+
+    let value = expr.split(',').find_map(|alternative| field_value(metadata, alternative));
+    match (value, replacement) {
+        (Some(value), Some(replacement)) => replacement.replace("{}", &format_value(&value, spec)),
+        (Some(value), None) => format_value(&value, spec),
+        (None, _) => default.unwrap_or(MISSING_VALUE).to_string(),
+    }
+}
+
+/// A field's value from the video JSON, if it has one
+///
+/// `field` may traverse objects and lists (`formats.0.height`) and format a
+/// date (`upload_date>%Y-%m-%d`).
+fn field_value(metadata: &VideoMetadata, field: &str) -> Option<Value> {
+    let (path, date_format) = match field.split_once('>') {
+        Some((path, date_format)) => (path, Some(date_format)),
+        None => (field, None),
+    };
+
+    let mut value = &metadata.raw;
+    for key in path.split('.') {
+        value = match value {
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            value => value.get(key)?,
+        };
+    }
+    if value.is_null() {
+        return None;
+    }
+
+    let Some(date_format) = date_format else {
+        return Some(value.clone());
+    };
+    let date = match value {
+        Value::String(text) => {
+            NaiveDate::parse_from_str(text, "%Y%m%d").ok()?.and_hms_opt(0, 0, 0)?
+        }
+        Value::Number(number) => DateTime::from_timestamp(number.as_f64()? as i64, 0)?.naive_utc(),
+        _ => return None,
+    };
+    // An unknown specifier makes formatting fail rather than panic this way
+    let mut formatted = String::new();
+    write!(formatted, "{}", date.format(date_format)).ok()?;
+    Some(Value::String(formatted))
+}
+
+/// Format a value with a printf-style spec
+fn format_value(value: &Value, spec: &Spec) -> String {
+    let mut text = match value {
+        Value::Number(number) if spec.is_integer() => {
+            number.as_f64().map(|n| (n.trunc() as i64).to_string()).unwrap_or_default()
+        }
+        Value::Number(number) if matches!(spec.conversion, 'f' | 'F') => number
+            .as_f64()
+            .map(|n| format!("{:.*}", spec.precision.unwrap_or(6), n))
+            .unwrap_or_default(),
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    };
+    if let (Some(precision), 's') = (spec.precision, spec.conversion) {
+        text = text.chars().take(precision).collect();
+    }
+
+    let padding = spec.width.saturating_sub(text.chars().count());
+    if padding == 0 {
+        text
+    } else if spec.left_align {
+        format!("{}{}", text, " ".repeat(padding))
+    } else if spec.zero_pad && spec.is_integer() {
+        format!("{}{}", "0".repeat(padding), text)
+    } else {
+        format!("{}{}", " ".repeat(padding), text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_presets() {
+        let metadata = sample_metadata();
+        let render = |template: &str| template.parse::<OutputTemplate>().unwrap().render(&metadata);
+
+        assert_eq!(render(DEFAULT_TEMPLATE), "Never Gonna Give You Up.mp4");
+        assert_eq!(
+            render("%(playlist_title)s/%(playlist_index)03d - %(title)s.%(ext)s"),
+            "Favourites/007 - Never Gonna Give You Up.mp4"
+        );
+        assert_eq!(render("%(upload_date)s 100%% %(title)s.%(ext)s"), "20091025 100% Never Gonna Give You Up.mp4");
+
+        for (_, preset) in PRESETS {
+            assert!(preset.parse::<OutputTemplate>().is_ok(), "{}", preset);
+        }
+    }
+
+    #[test]
+    fn test_missing_fields_and_separators() {
+        let mut metadata = sample_metadata();
+        metadata.raw["uploader"] = Value::String("AC/DC".to_string());
+        metadata.raw["playlist_index"] = Value::Null;
+
+        let template: OutputTemplate = "%(uploader)s/%(playlist_index)s.%(ext)s".parse().unwrap();
+        assert_eq!(template.render(&metadata), "AC_DC/NA.mp4");
+    }
+
+    #[test]
+    fn test_validate_templates() {
+        for template in [
+            "",
+            "%(title)s",
+            "%(title)s.%(ext)s/extra",
+            "%()s.%(ext)s",
+            "%(title.%(ext)s",
+            "%(title)5.%(ext)s",
+            "%(title).%(ext)s",
+            "50% %(title)s.%(ext)s",
+            "/abs/%(title)s.%(ext)s",
+            "../%(title)s.%(ext)s",
+        ] {
+            assert!(template.parse::<OutputTemplate>().is_err(), "{}", template);
+        }
+
+        for template in [
+            "%(series)s - %(episode_number)02d.%(ext)s",
+            "%(title).50s [%(id)s].%(ext)s",
+            "%(upload_date>%Y)s/%(title)s.%(ext)s",
+            "%(uploader,channel|Unknown)s/%(title)s.%(ext)s",
+            "%(title)-20S.%(ext)s",
+        ] {
+            assert!(template.parse::<OutputTemplate>().is_ok(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_render_field_syntax() {
+        let mut metadata = sample_metadata();
+        metadata.raw["uploader"] = Value::Null;
+        let render = |template: &str| template.parse::<OutputTemplate>().unwrap().render(&metadata);
+
+        assert_eq!(render("%(title).5s.%(ext)s"), "Never.mp4");
+        assert_eq!(render("%(upload_date>%Y)s/%(title).5s.%(ext)s"), "2009/Never.mp4");
+        assert_eq!(render("%(timestamp>%Y-%m-%d)s.%(ext)s"), "2009-10-25.mp4");
+        assert_eq!(render("%(uploader|Unknown)s.%(ext)s"), "Unknown.mp4");
+        assert_eq!(render("%(uploader,channel)s.%(ext)s"), "Rick Astley.mp4");
+        assert_eq!(render("%(playlist_index&#{} |)s%(id)s.%(ext)s"), "#7 dQw4w9WgXcQ.mp4");
+        assert_eq!(render("%(series)s.%(ext)s"), "NA.mp4");
+    }
+
+    #[test]
+    fn test_invalid_template_loads_and_falls_back() {
+        let template: OutputTemplate = serde_json::from_str("\"%(title)s\"").unwrap();
+        assert_eq!(template.as_str(), "%(title)s");
+        assert_eq!(serde_json::to_string(&template).unwrap(), "\"%(title)s\"");
+
+        let output = template.output_arg(Path::new("/data/videos"));
+        assert_eq!(Path::new(&output), Path::new("/data/videos").join(DEFAULT_TEMPLATE));
+    }
+
+    #[test]
+    fn test_output_arg_joins_with_separator() {
+        let template = OutputTemplate::default();
+        let without_slash = template.output_arg(Path::new("/data/videos"));
+        let with_slash = template.output_arg(Path::new("/data/videos/"));
+        assert_eq!(Path::new(&without_slash), Path::new("/data/videos/%(title)s.%(ext)s"));
+        assert_eq!(Path::new(&with_slash), Path::new("/data/videos/%(title)s.%(ext)s"));
+
+        let escaped = template.output_arg(Path::new("/data/100% videos"));
+        assert_eq!(Path::new(&escaped), Path::new("/data/100%% videos/%(title)s.%(ext)s"));
+    }
+}
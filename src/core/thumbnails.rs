@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::error::{Result, YtdlError};
+
+/// Default size limit for the thumbnail cache (50 MiB)
+pub const DEFAULT_CACHE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// On-disk cache of video thumbnails keyed by video id
+///
+/// The cache is size bounded: once it grows past `max_bytes` the least
+/// recently used thumbnails are evicted.
+#[derive(Debug, Clone)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Cache in the platform cache directory
+    pub fn with_default_dir(max_bytes: u64) -> Result<Self> {
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| YtdlError::Thumbnail("Could not find cache directory".to_string()))?;
+
+        Ok(Self::new(cache_dir.join("ytdl-mini").join("thumbnails"), max_bytes))
+    }
+
+    /// Path of the cached thumbnail for a video, if there is one
+    pub fn cached_path(&self, video_id: &str) -> Option<PathBuf> {
+        let entries = std::fs::read_dir(&self.dir).ok()?;
+        let path = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.file_stem().is_some_and(|stem| stem == video_id))?;
+
+        // Mark as recently used so eviction keeps it
+        touch(&path);
+        Some(path)
+    }
+
+    /// Return the cached thumbnail, downloading it first if needed
+    pub async fn fetch(&self, video_id: &str, url: &str) -> Result<PathBuf> {
+        if let Some(path) = self.cached_path(video_id) {
+            return Ok(path);
+        }
+
+        let fetch_error = |e: reqwest::Error| YtdlError::Thumbnail(format!("{}: {}", url, e));
+        let response = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(fetch_error)?;
+        let bytes = response.bytes().await.map_err(fetch_error)?;
+        self.store(video_id, thumbnail_extension(url), &bytes)
+    }
+
+    /// Write a thumbnail into the cache and evict old entries if over the limit
+    pub fn store(&self, video_id: &str, extension: &str, bytes: &[u8]) -> Result<PathBuf> {
+        if video_id.is_empty() || video_id.contains(['/', '\\', '.']) {
+            return Err(YtdlError::Thumbnail(format!("Invalid video id: {}", video_id)));
+        }
+
+        let path = self.dir.join(format!("{}.{}", video_id, extension));
+        crate::utils::file_utils::write_atomic(&path, bytes)?;
+        self.enforce_limit(&path)?;
+        Ok(path)
+    }
+
+    /// Evict least recently used thumbnails until the cache fits, never evicting `keep`
+    fn enforce_limit(&self, keep: &Path) -> Result<()> {
+        let mut entries: Vec<(PathBuf, u64, SystemTime)> = std::fs::read_dir(&self.dir)?
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    (entry.path(), metadata.len(), modified)
+                })
+            })
+            .collect();
+
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path != keep && std::fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Guess the image extension from a thumbnail URL, defaulting to jpg
+fn thumbnail_extension(url: &str) -> &'static str {
+    let path = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+    match path.rsplit('.').next() {
+        Some("webp") => "webp",
+        Some("png") => "png",
+        _ => "jpg",
+    }
+}
+
+/// Update a file's modification time to now
+fn touch(path: &Path) {
+    if let Ok(file) = File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_utils::TempDir;
+
+    #[test]
+    fn test_thumbnail_extension() {
+        assert_eq!(thumbnail_extension("https://i.ytimg.com/vi/abc/maxresdefault.webp"), "webp");
+        assert_eq!(thumbnail_extension("https://i.ytimg.com/vi/abc/hqdefault.jpg?sqp=x.png"), "jpg");
+        assert_eq!(thumbnail_extension("https://example.com/thumb"), "jpg");
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let temp = TempDir::new();
+        let dir = temp.path().join("thumbnails");
+        let cache = ThumbnailCache::new(dir.clone(), 25);
+
+        cache.store("first", "jpg", &[0; 10]).unwrap();
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        File::options().append(true).open(dir.join("first.jpg")).unwrap().set_modified(old).unwrap();
+        cache.store("second", "jpg", &[0; 10]).unwrap();
+        cache.store("third", "webp", &[0; 10]).unwrap();
+
+        assert!(cache.cached_path("first").is_none());
+        assert!(cache.cached_path("second").is_some());
+        assert_eq!(cache.cached_path("third"), Some(dir.join("third.webp")));
+        assert!(cache.store("../escape", "jpg", &[0; 1]).is_err());
+    }
+}
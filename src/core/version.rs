@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use super::YtdlError;
+
+/// Oldest yt-dlp release known to work with YouTube's current player
+pub const MIN_SUPPORTED_VERSION: &str = "2024.08.06";
+
+/// A yt-dlp version such as `2024.08.06` or, for nightly and master builds,
+/// `2024.08.07.232541`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YtDlpVersion {
+    parts: Vec<u32>,
+}
+
+impl YtDlpVersion {
+    /// The oldest version downloads are allowed to run with
+    pub fn minimum_supported() -> Self {
+        MIN_SUPPORTED_VERSION.parse().expect("MIN_SUPPORTED_VERSION is a valid version")
+    }
+
+    /// Whether this version is new enough to use
+    pub fn is_supported(&self) -> bool {
+        *self >= Self::minimum_supported()
+    }
+}
+
+impl FromStr for YtDlpVersion {
+    type Err = YtdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || YtdlError::InvalidVersion(s.trim().to_string());
+        let parts = s
+            .trim()
+            .split('.')
+            .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Year, month and day are always present
+        if parts.len() < 3 {
+            return Err(invalid());
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl Ord for YtDlpVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.parts.cmp(&other.parts)
+    }
+}
+
+impl PartialOrd for YtDlpVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for YtDlpVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            match i {
+                0 => write!(f, "{}", part)?,
+                // Month and day are zero padded like yt-dlp's own tags
+                1 | 2 => write!(f, ".{:02}", part)?,
+                _ => write!(f, ".{}", part)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which line of yt-dlp builds to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    /// Tagged releases
+    #[default]
+    Stable,
+    /// Daily builds with the latest extractor fixes
+    Nightly,
+    /// A build of every commit to master
+    Master,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 3] = [
+        UpdateChannel::Stable,
+        UpdateChannel::Nightly,
+        UpdateChannel::Master,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Nightly => "nightly",
+            UpdateChannel::Master => "master",
+        }
+    }
+
+    /// Repository that publishes this channel's releases
+    pub fn repository(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "yt-dlp/yt-dlp",
+            UpdateChannel::Nightly => "yt-dlp/yt-dlp-nightly-builds",
+            UpdateChannel::Master => "yt-dlp/yt-dlp-master-builds",
+        }
+    }
+}
+
+impl FromStr for UpdateChannel {
+    type Err = YtdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|channel| channel.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| YtdlError::InvalidSetting(format!("Unknown update channel: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compare_versions() {
+        let stable: YtDlpVersion = "2024.08.06".parse().unwrap();
+        let nightly: YtDlpVersion = "2024.08.06.232541\n".parse().unwrap();
+        let old: YtDlpVersion = "2023.3.4".parse().unwrap();
+
+        assert!(old < stable);
+        assert!(stable < nightly);
+        assert_eq!(old.to_string(), "2023.03.04");
+        assert_eq!(nightly.to_string(), "2024.08.06.232541");
+
+        assert!(stable.is_supported());
+        assert!(!old.is_supported());
+        assert!("2024.08".parse::<YtDlpVersion>().is_err());
+        assert!("not a version".parse::<YtDlpVersion>().is_err());
+    }
+
+    #[test]
+    fn test_parse_update_channel() {
+        assert_eq!("Nightly".parse::<UpdateChannel>().unwrap(), UpdateChannel::Nightly);
+        assert_eq!(UpdateChannel::Master.repository(), "yt-dlp/yt-dlp-master-builds");
+        assert!("beta".parse::<UpdateChannel>().is_err());
+    }
+}
@@ -1,8 +1,33 @@
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use serde::{Deserialize, Serialize};
-use anyhow::{anyhow, Result};
-use tokio::process::Command as TokioCommand;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command as TokioCommand};
+use tokio::sync::{mpsc, oneshot};
+
+use super::error::Result;
+use super::command::validate_extra_args;
+use super::installer::{Release, YtDlpInstaller, DEFAULT_RELEASE_INDEX};
+use super::version::MIN_SUPPORTED_VERSION;
+use super::collision::{avoid_collision, existing_file, replace_existing};
+use super::{
+    CollisionOutcome, CollisionPolicy, DownloadFailure, DownloadMode, DownloadOptions, Format,
+    Playlist, Settings, UpdateChannel, YtDlpCommand, YtDlpVersion, YtdlError,
+};
+
+/// Marker that prefixes our own progress lines in yt-dlp output
+const PROGRESS_PREFIX: &str = "ytdl-mini-progress";
+
+/// Marker that prefixes the final file path yt-dlp prints
+const FILE_PREFIX: &str = "ytdl-mini-file ";
+
+/// Print the final path once the file is in place, after merging, extraction and remuxing
+const FILE_TEMPLATE: &str = "after_move:ytdl-mini-file %(filepath)s";
+
+/// Progress template passed to yt-dlp so every update is a single parseable line
+const PROGRESS_TEMPLATE: &str = "download:ytdl-mini-progress \
+%(progress.downloaded_bytes)s %(progress.total_bytes)s %(progress.total_bytes_estimate)s \
+%(progress.speed)s %(progress.eta)s %(progress.fragment_index)s %(progress.fragment_count)s";
 
 /// Video metadata extracted from yt-dlp
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,103 +38,489 @@ pub struct VideoMetadata {
     pub upload_date: Option<String>,
     pub view_count: Option<u64>,
     pub thumbnail: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    pub channel_url: Option<String>,
+    #[serde(default)]
+    pub like_count: Option<u64>,
+    /// One of yt-dlp's live states, e.g. "not_live", "is_live" or "was_live"
+    #[serde(default)]
+    pub live_status: Option<String>,
+    #[serde(default)]
+    pub age_limit: Option<u32>,
+    /// The URL the metadata was requested for
+    #[serde(default)]
+    pub original_url: Option<String>,
+    /// Name of the yt-dlp extractor that handled the URL, e.g. "youtube"
+    #[serde(default)]
+    pub extractor: Option<String>,
+    /// Languages with uploaded subtitles
+    #[serde(default)]
+    pub subtitle_languages: Vec<String>,
+    /// Languages with auto-generated captions
+    #[serde(default)]
+    pub automatic_caption_languages: Vec<String>,
+    /// The full `--dump-json` document, for fields not modelled above
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub raw: serde_json::Value,
+}
+
+/// A chapter marker within a video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Start time in seconds
+    pub start_time: f64,
+    /// End time in seconds
+    pub end_time: f64,
+    pub title: String,
+}
+
+impl VideoMetadata {
+    /// Subtitle languages that can actually be downloaded for this video
+    pub fn available_subtitle_languages(&self, include_auto: bool) -> Vec<String> {
+        let mut languages = self.subtitle_languages.clone();
+        if include_auto {
+            languages.extend(self.automatic_caption_languages.iter().cloned());
+            languages.sort();
+            languages.dedup();
+        }
+        languages
+    }
+
+    /// Where yt-dlp plans to save the video, if the metadata came from
+    /// [`YtDlp::get_download_metadata`]
+    pub fn planned_path(&self) -> Option<PathBuf> {
+        let filename = self.raw.get("filename").or_else(|| self.raw.get("_filename"))?;
+        filename.as_str().map(PathBuf::from)
+    }
+
+    /// Build metadata from a `--dump-json` document
+    pub fn from_json(json_value: serde_json::Value) -> Self {
+        let as_string = |key: &str| json_value[key].as_str().map(|s| s.to_string());
+        let as_strings = |key: &str| -> Vec<String> {
+            json_value[key]
+                .as_array()
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let languages = |key: &str| -> Vec<String> {
+            let mut languages: Vec<String> = json_value[key]
+                .as_object()
+                .map(|subtitles| subtitles.keys().cloned().collect())
+                .unwrap_or_default();
+            languages.sort();
+            languages
+        };
+
+        let chapters = json_value["chapters"]
+            .as_array()
+            .map(|chapters| {
+                chapters
+                    .iter()
+                    .filter_map(|chapter| {
+                        Some(Chapter {
+                            start_time: chapter["start_time"].as_f64()?,
+                            end_time: chapter["end_time"].as_f64()?,
+                            title: chapter["title"].as_str().unwrap_or_default().to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            title: json_value["title"]
+                .as_str()
+                .unwrap_or("Unknown Title")
+                .to_string(),
+            duration: json_value["duration"].as_f64(),
+            uploader: as_string("uploader"),
+            upload_date: as_string("upload_date"),
+            view_count: json_value["view_count"].as_u64(),
+            thumbnail: as_string("thumbnail"),
+            id: as_string("id"),
+            description: as_string("description"),
+            tags: as_strings("tags"),
+            categories: as_strings("categories"),
+            chapters,
+            channel_id: as_string("channel_id"),
+            channel_url: as_string("channel_url"),
+            like_count: json_value["like_count"].as_u64(),
+            live_status: as_string("live_status"),
+            age_limit: json_value["age_limit"].as_u64().map(|v| v as u32),
+            original_url: as_string("original_url").or_else(|| as_string("webpage_url")),
+            extractor: as_string("extractor_key").or_else(|| as_string("extractor")),
+            subtitle_languages: languages("subtitles"),
+            automatic_caption_languages: languages("automatic_captions"),
+            raw: json_value,
+        }
+    }
+}
+
+/// A finished download
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompletedDownload {
+    /// The final file, after any merging, audio extraction or remuxing
+    pub file_path: Option<String>,
+    /// Size of the final file in bytes
+    pub file_size: Option<u64>,
+    /// Lowercase hex SHA-256 of the final file
+    pub sha256: Option<String>,
+    /// How an existing file was dealt with, if there was one
+    pub collision: Option<CollisionOutcome>,
+}
+
+/// How a running download should be stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMode {
+    /// Kill yt-dlp and remove its partial files
+    Cancel,
+    /// Kill yt-dlp but keep partial files so the download can continue later
+    Pause,
+}
+
+/// A single progress update reported by yt-dlp while downloading
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    /// Completion percentage (0.0 to 100.0), if it can be determined
+    pub percent: Option<f32>,
+    pub downloaded_bytes: Option<u64>,
+    /// Exact total size, or yt-dlp's estimate when the exact size is unknown
+    pub total_bytes: Option<u64>,
+    /// Download speed in bytes per second
+    pub speed: Option<f64>,
+    /// Estimated time remaining in seconds
+    pub eta: Option<u64>,
+    pub fragment_index: Option<u32>,
+    pub fragment_count: Option<u32>,
+}
+
+impl DownloadProgress {
+    /// Parse a line produced by our `--progress-template`
+    ///
+    /// Returns `None` for any other yt-dlp output. Fields yt-dlp doesn't know
+    /// are printed as `NA` and end up as `None`.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix(PROGRESS_PREFIX)?;
+        let fields: Vec<Option<f64>> = rest
+            .split_whitespace()
+            .map(|value| value.parse::<f64>().ok().filter(|v| v.is_finite()))
+            .collect();
+
+        if fields.len() != 7 {
+            return None;
+        }
+
+        let downloaded_bytes = fields[0].map(|v| v as u64);
+        let total_bytes = fields[1].or(fields[2]).map(|v| v as u64);
+        let fragment_index = fields[5].map(|v| v as u32);
+        let fragment_count = fields[6].map(|v| v as u32);
+
+        let percent = match (downloaded_bytes, total_bytes, fragment_index, fragment_count) {
+            (Some(done), Some(total), _, _) if total > 0 => Some(done as f32 / total as f32 * 100.0),
+            (_, _, Some(index), Some(count)) if count > 0 => Some(index as f32 / count as f32 * 100.0),
+            _ => None,
+        }
+        .map(|p| p.clamp(0.0, 100.0));
+
+        Some(Self {
+            percent,
+            downloaded_bytes,
+            total_bytes,
+            speed: fields[3],
+            eta: fields[4].map(|v| v as u64),
+            fragment_index,
+            fragment_count,
+        })
+    }
+
+    /// Completion as a fraction from 0.0 to 1.0
+    pub fn fraction(&self) -> Option<f32> {
+        self.percent.map(|p| p / 100.0)
+    }
+}
+
+/// Result of comparing the yt-dlp in use with the latest release of a channel
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub channel: UpdateChannel,
+    pub current: YtDlpVersion,
+    pub latest: Release,
+}
+
+impl UpdateCheck {
+    /// Whether the latest release is newer than the version in use
+    pub fn update_available(&self) -> bool {
+        self.latest
+            .version
+            .parse::<YtDlpVersion>()
+            .is_ok_and(|latest| latest > self.current)
+    }
 }
 
 /// YT-DLP wrapper for managing video downloads
 pub struct YtDlp {
-    executable_path: Option<PathBuf>,
+    executable: Option<YtDlpCommand>,
+    /// Command from settings that replaces the managed and PATH binaries
+    custom_command: Option<YtDlpCommand>,
+    /// Extra arguments added to every call except `--version`
+    extra_args: Vec<String>,
+    /// Release index the managed binary is installed and updated from
+    release_index: String,
+    channel: UpdateChannel,
 }
 
 impl YtDlp {
     /// Create a new YT-DLP instance
     pub fn new() -> Self {
         Self {
-            executable_path: None,
+            executable: None,
+            custom_command: None,
+            extra_args: Vec::new(),
+            release_index: DEFAULT_RELEASE_INDEX.to_string(),
+            channel: UpdateChannel::default(),
         }
     }
 
-    /// Initialize yt-dlp (check if installed, install if needed)
+    /// Create an instance using the command, extra arguments and release index from settings
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            executable: None,
+            custom_command: settings.ytdlp_command.clone(),
+            extra_args: settings.ytdlp_extra_args.clone(),
+            release_index: settings.ytdlp_release_index.clone(),
+            channel: settings.update_channel,
+        }
+    }
+
+    fn installer(&self) -> Result<YtDlpInstaller> {
+        YtDlpInstaller::with_default_dir(self.release_index.clone(), self.channel)
+    }
+
+    /// Initialize yt-dlp, installing the managed binary if none is found
+    /// or the one found is older than the minimum supported version
+    ///
+    /// A custom command from settings is used as-is and never replaced.
     pub async fn initialize(&mut self) -> Result<()> {
-        // First, try to find yt-dlp in PATH
-        if let Ok(path) = which::which("yt-dlp") {
-            self.executable_path = Some(path);
+        validate_extra_args(&self.extra_args)?;
+
+        if let Some(custom) = &self.custom_command {
+            self.executable = Some(custom.resolve()?);
+            let version = self.version().await?;
+            if !version.is_supported() {
+                return Err(YtdlError::UnsupportedVersion {
+                    found: version.to_string(),
+                    minimum: MIN_SUPPORTED_VERSION.to_string(),
+                });
+            }
             return Ok(());
         }
 
-        // If not found, try to install it
-        self.install_ytdlp().await?;
-        
-        // Try to find it again after installation
-        if let Ok(path) = which::which("yt-dlp") {
-            self.executable_path = Some(path);
-            Ok(())
+        let installer = self.installer()?;
+
+        // Prefer our own verified copy, then one on PATH
+        let path = if installer.is_installed() {
+            Some(installer.installed_path())
         } else {
-            Err(anyhow!("Failed to install or find yt-dlp"))
+            which::which("yt-dlp").ok()
+        };
+        let path = match path {
+            Some(path) => path,
+            None => installer.install().await?,
+        };
+        self.executable = Some(YtDlpCommand::new(path));
+
+        let version = self.version().await?;
+        if !version.is_supported() {
+            log::warn!(
+                "yt-dlp {} is older than {}, installing the latest {} build",
+                version,
+                MIN_SUPPORTED_VERSION,
+                self.channel.as_str()
+            );
+            match installer.install().await {
+                Ok(path) => self.executable = Some(YtDlpCommand::new(path)),
+                Err(e) => {
+                    log::error!("Failed to update yt-dlp: {}", e);
+                    return Err(YtdlError::UnsupportedVersion {
+                        found: version.to_string(),
+                        minimum: MIN_SUPPORTED_VERSION.to_string(),
+                    });
+                }
+            }
         }
+        Ok(())
+    }
+
+    /// Process builder for the yt-dlp in use, without extra arguments
+    fn base_command(&self) -> Result<TokioCommand> {
+        self.executable
+            .as_ref()
+            .map(YtDlpCommand::to_command)
+            .ok_or(YtdlError::ExecutableMissing)
+    }
+
+    /// Process builder for the yt-dlp in use, with the extra arguments from settings
+    fn command(&self) -> Result<TokioCommand> {
+        let mut command = self.base_command()?;
+        command.args(&self.extra_args);
+        Ok(command)
     }
 
-    /// Install yt-dlp using pip
-    async fn install_ytdlp(&self) -> Result<()> {
-        log::info!("Installing yt-dlp...");
-        
-        let output = TokioCommand::new("pip")
-            .args(&["install", "yt-dlp"])
+    /// Version of the yt-dlp in use
+    pub async fn version(&self) -> Result<YtDlpVersion> {
+        let output = self.base_command()?
+            .arg("--version")
             .output()
-            .await?;
+            .await
+            .map_err(YtdlError::Spawn)?;
 
         if output.status.success() {
-            log::info!("yt-dlp installed successfully");
-            Ok(())
+            String::from_utf8_lossy(&output.stdout).parse()
         } else {
             let error = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow!("Failed to install yt-dlp: {}", error))
+            Err(YtdlError::ProcessFailed {
+                context: "Failed to get yt-dlp version",
+                stderr: error.into_owned(),
+            })
         }
     }
 
+    /// Compare the version in use with the latest release of the channel
+    pub async fn check_for_update(&self) -> Result<UpdateCheck> {
+        let current = self.version().await?;
+        let latest = self.installer()?.latest_release().await?;
+
+        Ok(UpdateCheck { channel: self.channel, current, latest })
+    }
+
+    /// Install the latest release of `channel` as the managed binary
+    ///
+    /// Switching channels installs that channel's latest build even if it is
+    /// older than the current one, e.g. when going from nightly back to stable.
+    pub async fn update(&mut self, channel: UpdateChannel) -> Result<YtDlpVersion> {
+        if self.custom_command.is_some() {
+            return Err(YtdlError::InvalidState(
+                "yt-dlp runs with a custom command and can't be updated by ytdl-mini",
+            ));
+        }
+
+        self.channel = channel;
+        let installer = self.installer()?;
+        let release = installer.latest_release().await?;
+
+        let managed = self.executable == Some(YtDlpCommand::new(installer.installed_path()));
+        if managed {
+            let current = self.version().await?;
+            if release.version.parse().ok() == Some(current.clone()) {
+                log::info!("yt-dlp {} is already the latest {} build", current, channel.as_str());
+                return Ok(current);
+            }
+        }
+
+        self.executable = Some(YtDlpCommand::new(installer.install_release(&release).await?));
+        self.version().await
+    }
+
     /// Check if yt-dlp is available
     pub fn is_available(&self) -> bool {
-        self.executable_path.is_some()
+        self.executable.is_some()
     }
 
     /// Get video metadata without downloading
     pub async fn get_metadata(&self, url: &str) -> Result<VideoMetadata> {
-        let executable = self.executable_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not available"))?;
+        let json_value = self.dump_json(url, "Failed to get metadata").await?;
 
-        let output = TokioCommand::new(executable)
-            .args(&[
+        Ok(VideoMetadata::from_json(json_value))
+    }
+
+    /// Metadata of a video as [`download_video_with_progress`](Self::download_video_with_progress)
+    /// would save it under `output_path`
+    ///
+    /// The JSON also has the `filename` yt-dlp plans to write, so the
+    /// download can check for an existing file without another extraction.
+    pub async fn get_download_metadata(
+        &self,
+        url: &str,
+        output_path: &Path,
+        options: &DownloadOptions,
+    ) -> Result<VideoMetadata> {
+        let mut args = options.to_args();
+        args.extend([
+            "--output".to_string(),
+            options.output_template.output_arg(output_path),
+            "--no-playlist".to_string(),
+        ]);
+        let json_value = self.dump_json_with(url, &args, "Failed to get metadata").await?;
+
+        Ok(VideoMetadata::from_json(json_value))
+    }
+
+    /// Run `--dump-json` for a single video and parse the result
+    async fn dump_json(&self, url: &str, context: &'static str) -> Result<serde_json::Value> {
+        self.dump_json_with(url, &[], context).await
+    }
+
+    /// Run `--dump-json` with extra arguments, such as format selection
+    async fn dump_json_with(
+        &self,
+        url: &str,
+        args: &[String],
+        context: &'static str,
+    ) -> Result<serde_json::Value> {
+        let output = self.command()?
+            .args(args)
+            .args([
                 "--dump-json",
                 "--no-download",
                 url
             ])
             .output()
-            .await?;
+            .await
+            .map_err(YtdlError::Spawn)?;
 
         if output.status.success() {
-            let json_str = String::from_utf8(output.stdout)?;
-            let json_value: serde_json::Value = serde_json::from_str(&json_str)?;
-            
-            Ok(VideoMetadata {
-                title: json_value["title"]
-                    .as_str()
-                    .unwrap_or("Unknown Title")
-                    .to_string(),
-                duration: json_value["duration"].as_f64(),
-                uploader: json_value["uploader"]
-                    .as_str()
-                    .map(|s| s.to_string()),
-                upload_date: json_value["upload_date"]
-                    .as_str()
-                    .map(|s| s.to_string()),
-                view_count: json_value["view_count"].as_u64(),
-                thumbnail: json_value["thumbnail"]
-                    .as_str()
-                    .map(|s| s.to_string()),
-            })
+            let json_str = String::from_utf8_lossy(&output.stdout);
+            Ok(serde_json::from_str(&json_str)?)
         } else {
             let error = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow!("Failed to get metadata: {}", error))
+            Err(YtdlError::ProcessFailed { context, stderr: error.into_owned() })
+        }
+    }
+
+    /// List the videos of a playlist, channel or mix without resolving each one
+    pub async fn get_playlist(&self, url: &str) -> Result<Playlist> {
+        let url = crate::utils::normalize_collection_url(url);
+        let output = self.command()?
+            .args(["--flat-playlist", "--dump-json", &url])
+            .output()
+            .await
+            .map_err(YtdlError::Spawn)?;
+
+        if output.status.success() {
+            Ok(Playlist::from_json_lines(&String::from_utf8_lossy(&output.stdout))?)
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            Err(YtdlError::ProcessFailed {
+                context: "Failed to list playlist",
+                stderr: error.into_owned(),
+            })
         }
     }
 
@@ -119,52 +530,243 @@ impl YtDlp {
         url: &str,
         output_path: &PathBuf,
         resolution: &str,
-    ) -> Result<String> {
-        let executable = self.executable_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not available"))?;
+    ) -> Result<CompletedDownload> {
+        let (progress_tx, _progress_rx) = mpsc::unbounded_channel();
+        let (_stop_tx, mut stop_rx) = oneshot::channel();
+        let options = DownloadOptions {
+            mode: DownloadMode::Video { resolution: resolution.to_string() },
+            ..Default::default()
+        };
+        self.download_video_with_progress(
+            url,
+            output_path,
+            &options,
+            None,
+            progress_tx,
+            &mut stop_rx,
+            &mut Vec::new(),
+        )
+        .await
+    }
+
+    /// Where yt-dlp would save a download, and the video's id, without downloading it
+    ///
+    /// Takes an extraction of its own; used when no metadata from
+    /// [`get_download_metadata`](Self::get_download_metadata) is at hand.
+    async fn planned_file(
+        &self,
+        url: &str,
+        output: &str,
+        options: &DownloadOptions,
+    ) -> Result<(PathBuf, String)> {
+        let result = self.command()?
+            .args(options.to_args())
+            .args(["--output", output, "--no-playlist", "--simulate"])
+            .args(["--print", "filename", "--print", "id", url])
+            .output()
+            .await
+            .map_err(YtdlError::Spawn)?;
+
+        if !result.status.success() {
+            return Err(DownloadFailure::from_stderr(&String::from_utf8_lossy(&result.stderr)).into());
+        }
+
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        let mut lines = stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+        match (lines.next(), lines.next()) {
+            (Some(file), Some(id)) => Ok((PathBuf::from(file), id.to_string())),
+            _ => Err(YtdlError::ProcessFailed {
+                context: "Failed to plan the download's file name",
+                stderr: stdout.into_owned(),
+            }),
+        }
+    }
+
+    /// Download a video or its audio, streaming progress updates as yt-dlp reports them
+    ///
+    /// An existing file with the planned name is handled by the options'
+    /// collision policy. Sending on `stop` kills the yt-dlp process tree. Partial files are
+    /// removed for [`StopMode::Cancel`] and kept for [`StopMode::Pause`];
+    /// dropping the sender counts as a cancel.
+    ///
+    /// Files yt-dlp starts writing are added to `partials`, so callers can
+    /// still clean them up after this attempt has returned. `metadata` from
+    /// [`get_download_metadata`](Self::get_download_metadata) saves planning
+    /// the file name separately.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_video_with_progress(
+        &self,
+        url: &str,
+        output_path: &PathBuf,
+        options: &DownloadOptions,
+        metadata: Option<&VideoMetadata>,
+        progress: mpsc::UnboundedSender<DownloadProgress>,
+        stop: &mut oneshot::Receiver<StopMode>,
+        partials: &mut Vec<PathBuf>,
+    ) -> Result<CompletedDownload> {
+        let mut command = self.command()?;
 
         // Ensure output directory exists
         crate::utils::file_utils::ensure_dir_exists(output_path)?;
 
-        // Build format selector for the desired resolution
-        let format_selector = format!("best[height<={}]", 
-            resolution.split('x').nth(1).unwrap_or("1080"));
+        // Look for an existing file before yt-dlp picks the name
+        let mut output = options.output_template.output_arg(output_path);
+        let mut collision = None;
+        let mut replaces = None;
+        let planned =
+            metadata.and_then(|metadata| Some((metadata.planned_path()?, metadata.id.clone()?)));
+        let (planned, video_id) = match planned {
+            Some(planned) => planned,
+            None => self.planned_file(url, &output, options).await?,
+        };
+        let extensions = options.mode.output_extensions(&planned);
+        match (options.collision_policy, existing_file(&planned, &extensions)) {
+            (_, None) => {}
+            (CollisionPolicy::Skip, Some(existing)) => {
+                log::info!("{} already exists, skipping {}", existing.display(), url);
+                let mut completed = CompletedDownload {
+                    file_path: Some(existing.to_string_lossy().into_owned()),
+                    collision: Some(CollisionOutcome::Skipped),
+                    ..Default::default()
+                };
+                fingerprint(&mut completed).await;
+                return Ok(completed);
+            }
+            (policy, Some(existing)) => {
+                if let Some((renamed, outcome)) =
+                    avoid_collision(&planned, &extensions, policy, Some(&video_id))
+                {
+                    log::info!("{} exists, saving the download under another name", planned.display());
+                    output = renamed;
+                    collision = Some(outcome);
+                    // The existing file stays until the new one is complete
+                    if policy == CollisionPolicy::Overwrite {
+                        replaces = Some(existing);
+                    }
+                }
+            }
+        }
 
-        let output = TokioCommand::new(executable)
-            .args(&[
-                "--format", &format_selector,
-                "--output", &format!("{}%(title)s.%(ext)s", output_path.to_string_lossy()),
-                "--merge-output-format", "mp4",
+        command
+            .args(options.to_args())
+            .args([
+                "--output", &output,
+                "--no-overwrites",
+                // Playlists are expanded into separate items before they get here
+                "--no-playlist",
+                // Pick up from existing .part files when a paused download resumes
+                "--continue",
+                "--newline",
+                "--progress-template", PROGRESS_TEMPLATE,
+                "--print", FILE_TEMPLATE,
+                // --print would otherwise silence progress and status messages
+                "--no-quiet",
                 url
             ])
-            .output()
-            .await?;
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
 
-        if output.status.success() {
-            // Parse the output to get the actual filename
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            
-            // Look for the download completion message
-            for line in stdout.lines() {
-                if line.contains("has already been downloaded") || 
-                   line.contains("Destination:") {
-                    // Extract filename from the line
-                    if let Some(filename) = self.extract_filename_from_output(line) {
-                        return Ok(filename);
-                    }
+        // Run yt-dlp in its own process group so ffmpeg children can be killed with it
+        #[cfg(unix)]
+        command.process_group(0);
+
+        let mut child = command.spawn().map_err(YtdlError::Spawn)?;
+
+        let stdout = child.stdout
+            .take()
+            .ok_or_else(|| YtdlError::Spawn(std::io::Error::other("Failed to capture yt-dlp output")))?;
+        let mut stderr = child.stderr
+            .take()
+            .ok_or_else(|| YtdlError::Spawn(std::io::Error::other("Failed to capture yt-dlp errors")))?;
+
+        // Drain stderr concurrently so yt-dlp never blocks on a full pipe
+        let stderr_task = tokio::spawn(async move {
+            let mut error = String::new();
+            let _ = stderr.read_to_string(&mut error).await;
+            error
+        });
+
+        // Forward progress lines as they arrive and keep everything else for parsing
+        let mut lines = BufReader::new(stdout).lines();
+        let mut output_lines = Vec::new();
+        let mut stopped = None;
+        loop {
+            tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => match DownloadProgress::parse_line(&line) {
+                        Some(update) => {
+                            // The receiver may have gone away; the download carries on regardless
+                            let _ = progress.send(update);
+                        }
+                        None => output_lines.push(line),
+                    },
+                    None => break,
+                },
+                mode = &mut *stop, if stopped.is_none() => {
+                    kill_process_tree(&mut child);
+                    stopped = Some(mode.unwrap_or(StopMode::Cancel));
+                }
+            }
+        }
+
+        let status = child.wait().await?;
+        let error = stderr_task.await.unwrap_or_default();
+
+        for line in &output_lines {
+            if let Some(destination) = self.extract_filename_from_output(line) {
+                let destination = PathBuf::from(destination);
+                if !partials.contains(&destination) {
+                    partials.push(destination);
                 }
             }
-            
-            // Fallback: return a generic success message
-            Ok("Download completed".to_string())
+        }
+
+        match stopped {
+            Some(StopMode::Cancel) => {
+                for destination in partials.iter() {
+                    crate::utils::file_utils::remove_partial_files(destination);
+                }
+                return Err(YtdlError::Cancelled);
+            }
+            Some(StopMode::Pause) => return Err(YtdlError::Paused),
+            None => {}
+        }
+
+        if status.success() {
+            let mut completed = CompletedDownload { collision, ..Default::default() };
+            let mut skipped_file = None;
+            for line in &output_lines {
+                if let Some(path) = line.strip_prefix(FILE_PREFIX) {
+                    completed.file_path = Some(path.trim().to_string());
+                } else if let Some(existing) = already_downloaded(line) {
+                    skipped_file = Some(existing.to_string());
+                    completed.collision = Some(CollisionOutcome::Skipped);
+                }
+            }
+            completed.file_path = completed.file_path.or(skipped_file);
+
+            if let (Some(existing), Some(path)) = (&replaces, &completed.file_path) {
+                log::info!("Replacing {}", existing.display());
+                let replaced = replace_existing(existing, Path::new(path))?;
+                completed.file_path = Some(replaced.to_string_lossy().into_owned());
+            }
+
+            if completed.file_path.is_some() {
+                fingerprint(&mut completed).await;
+            } else {
+                log::warn!("yt-dlp did not report where {} was saved", url);
+            }
+            Ok(completed)
         } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow!("Download failed: {}", error))
+            Err(DownloadFailure::from_stderr(&error).into())
         }
     }
 
-    /// Extract filename from yt-dlp output
+    /// Extract the file yt-dlp is writing from a `Destination:` line
+    ///
+    /// This is an intermediate name, such as a single format before merging,
+    /// and is only used to clean up partial files.
     fn extract_filename_from_output(&self, line: &str) -> Option<String> {
         // This is a simple implementation - in practice, you might want more robust parsing
         if let Some(start) = line.find("Destination: ") {
@@ -176,30 +778,58 @@ impl YtDlp {
     }
 
     /// Get available formats for a video
-    pub async fn get_formats(&self, url: &str) -> Result<Vec<String>> {
-        let executable = self.executable_path
-            .as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not available"))?;
-
-        let output = TokioCommand::new(executable)
-            .args(&["--list-formats", url])
-            .output()
-            .await?;
+    pub async fn get_formats(&self, url: &str) -> Result<Vec<Format>> {
+        let json_value = self.dump_json(url, "Failed to get formats").await?;
+        Ok(Format::list_from_json(&json_value))
+    }
+}
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let formats: Vec<String> = stdout
-                .lines()
-                .filter(|line| line.contains("mp4") || line.contains("webm"))
-                .map(|line| line.to_string())
-                .collect();
-            
-            Ok(formats)
-        } else {
-            let error = String::from_utf8_lossy(&output.stderr);
-            Err(anyhow!("Failed to get formats: {}", error))
+/// Record the size and checksum of a download's final file
+async fn fingerprint(completed: &mut CompletedDownload) {
+    let Some(path) = completed.file_path.clone() else {
+        return;
+    };
+    // Hashing a large video takes a while; keep it off the async workers
+    let fingerprint = tokio::task::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let size = std::fs::metadata(path)?.len();
+        let sha256 = crate::utils::file_utils::sha256_hex(std::fs::File::open(path)?)?;
+        Ok::<_, std::io::Error>((size, sha256))
+    })
+    .await;
+    match fingerprint {
+        Ok(Ok((size, sha256))) => {
+            completed.file_size = Some(size);
+            completed.sha256 = Some(sha256);
         }
+        Ok(Err(e)) => log::warn!("Could not read the downloaded file: {}", e),
+        Err(e) => log::warn!("Could not checksum the downloaded file: {}", e),
+    }
+}
+
+/// The file named by yt-dlp's `[download] <file> has already been downloaded` line
+fn already_downloaded(line: &str) -> Option<&str> {
+    line.strip_prefix("[download] ")?
+        .strip_suffix(" has already been downloaded")
+        .map(str::trim)
+}
+
+/// Kill a yt-dlp process together with any ffmpeg children it spawned
+fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        #[cfg(unix)]
+        let _ = Command::new("kill")
+            .args(["-TERM", &format!("-{}", pid)])
+            .status();
+
+        #[cfg(windows)]
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .status();
     }
+
+    // Make sure the direct child goes away even if the group kill failed
+    let _ = child.start_kill();
 }
 
 impl Default for YtDlp {
@@ -207,3 +837,96 @@ impl Default for YtDlp {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_already_downloaded() {
+        assert_eq!(
+            already_downloaded("[download] /videos/Title [id].mp4 has already been downloaded"),
+            Some("/videos/Title [id].mp4")
+        );
+        assert_eq!(already_downloaded("[download] Destination: /videos/Title.mp4"), None);
+    }
+
+    #[test]
+    fn test_planned_path() {
+        let metadata = VideoMetadata::from_json(serde_json::json!({
+            "title": "Video",
+            "filename": "/videos/Video.webm",
+        }));
+        assert_eq!(metadata.planned_path(), Some(PathBuf::from("/videos/Video.webm")));
+        assert_eq!(VideoMetadata::from_json(serde_json::json!({ "title": "Video" })).planned_path(), None);
+    }
+
+    #[test]
+    fn test_parse_progress_line() {
+        let progress = DownloadProgress::parse_line(
+            "ytdl-mini-progress 5242880 10485760 NA 1048576.5 5 NA NA",
+        )
+        .unwrap();
+
+        assert_eq!(progress.percent, Some(50.0));
+        assert_eq!(progress.downloaded_bytes, Some(5242880));
+        assert_eq!(progress.total_bytes, Some(10485760));
+        assert_eq!(progress.speed, Some(1048576.5));
+        assert_eq!(progress.eta, Some(5));
+        assert_eq!(progress.fragment_index, None);
+    }
+
+    #[test]
+    fn test_parse_progress_line_with_estimate_and_fragments() {
+        let progress =
+            DownloadProgress::parse_line("ytdl-mini-progress 1000 NA 4000.0 NA NA 3 12").unwrap();
+        assert_eq!(progress.total_bytes, Some(4000));
+        assert_eq!(progress.percent, Some(25.0));
+        assert_eq!(progress.fragment_index, Some(3));
+        assert_eq!(progress.fragment_count, Some(12));
+
+        let progress =
+            DownloadProgress::parse_line("ytdl-mini-progress NA NA NA NA NA 3 12").unwrap();
+        assert_eq!(progress.percent, Some(25.0));
+    }
+
+    #[test]
+    fn test_metadata_from_json() {
+        let metadata = VideoMetadata::from_json(serde_json::json!({
+            "id": "dQw4w9WgXcQ",
+            "title": "Lecture 1",
+            "tags": ["physics", "intro"],
+            "chapters": [
+                {"start_time": 0.0, "end_time": 60.5, "title": "Intro"},
+                {"start_time": 60.5, "end_time": 300.0, "title": "Kinematics"}
+            ],
+            "subtitles": {"en": [], "de": []},
+            "automatic_captions": {"fr": []},
+            "age_limit": 0,
+            "extractor_key": "Youtube",
+            "webpage_url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "playable_in_embed": true
+        }));
+
+        assert_eq!(metadata.id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(metadata.tags, ["physics", "intro"]);
+        assert_eq!(metadata.chapters.len(), 2);
+        assert_eq!(metadata.chapters[1].title, "Kinematics");
+        assert_eq!(metadata.subtitle_languages, ["de", "en"]);
+        assert_eq!(metadata.automatic_caption_languages, ["fr"]);
+        assert_eq!(metadata.available_subtitle_languages(true), ["de", "en", "fr"]);
+        assert_eq!(metadata.age_limit, Some(0));
+        assert_eq!(metadata.extractor.as_deref(), Some("Youtube"));
+        assert_eq!(
+            metadata.original_url.as_deref(),
+            Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ")
+        );
+        assert_eq!(metadata.raw["playable_in_embed"], true);
+    }
+
+    #[test]
+    fn test_parse_progress_ignores_other_output() {
+        assert_eq!(DownloadProgress::parse_line("[download] Destination: video.mp4"), None);
+        assert_eq!(DownloadProgress::parse_line("ytdl-mini-progress 1 2"), None);
+    }
+}
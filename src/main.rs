@@ -1,3 +1,5 @@
+mod api;
+mod cli;
 mod core;
 mod ui;
 mod utils;
@@ -6,14 +8,24 @@ use gpui::{
     px, size, App, AppContext, Application, Bounds, KeyBinding, TitlebarOptions, WindowBounds,
     WindowOptions,
 };
+use clap::Parser;
 use log::info;
 
 fn main() {
     // Initialize logging
     env_logger::init();
 
+    // Subcommands run headless; no subcommand opens the window
+    if let Some(command) = cli::Cli::parse().command {
+        std::process::exit(cli::run(command));
+    }
+
     info!("Starting ytdl-mini application");
 
+    // Downloads and update checks run on tokio; GPUI drives the main thread
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start async runtime");
+    let _runtime_guard = runtime.enter();
+
     // Create and run the GPUI application
     Application::new().run(|cx: &mut App| {
         // Initialize gpui-component
@@ -1,15 +1,35 @@
 use gpui::{
-    actions, div, prelude::*, px, rgb, App as GpuiApp, ClickEvent, Context, Entity, FocusHandle,
-    Focusable, IntoElement, Render, Subscription, Window,
+    actions, div, prelude::*, px, rgb, App as GpuiApp, ClickEvent, Context, Div, Entity,
+    FocusHandle, Focusable, IntoElement, Render, Subscription, Window,
 };
 
+use std::collections::{HashMap, HashSet};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
 use gpui_component::{
     button::{Button, ButtonVariants},
-    input::{InputState, TextInput},
+    input::{InputEvent, InputState, TextInput},
     Sizable,
 };
 
-use crate::core::{AppState, DownloadItem, DownloadStatus};
+use super::components;
+use super::folder_picker::NativeFolderPicker;
+use crate::core::template;
+use crate::core::{
+    choose_download_folder, AppState, CollisionPolicy, DownloadEvent, DownloadGroup, DownloadItem,
+    GroupProgress, GroupSummary, OutputTemplate, Playlist, PlaylistSelection, Settings,
+    VideoMetadata, YtdlError,
+};
+use crate::utils::expand_path;
+
+/// Resolutions offered in the settings panel
+const RESOLUTIONS: [(&str, &str); 4] = [
+    ("1920x1080", "1920x1080 (1080p)"),
+    ("1280x720", "1280x720 (720p)"),
+    ("2560x1440", "2560x1440 (1440p)"),
+    ("3840x2160", "3840x2160 (4K)"),
+];
 
 // Define actions for the app
 actions!(
@@ -26,8 +46,32 @@ actions!(
 /// Main application struct
 pub struct App {
     app_state: AppState,
+    /// The view's copy of the queue, kept current from download events
+    downloads: HashMap<Uuid, DownloadItem>,
+    /// Playlist groups the queued items belong to
+    groups: HashMap<Uuid, DownloadGroup>,
+    /// Why the last URL could not be added
+    add_error: Option<String>,
+    /// Whether a playlist URL is being expanded
+    listing_playlist: bool,
+    /// Expanded playlist waiting for the user to pick which entries to queue
+    playlist_choice: Option<PlaylistChoice>,
     url_input_state: Option<Entity<InputState>>,
+    /// Entries to queue from `playlist_choice`, e.g. "1-5,8"
+    playlist_items_state: Option<Entity<InputState>>,
     download_path_state: Option<Entity<InputState>>,
+    max_concurrent_state: Option<Entity<InputState>>,
+    output_template_state: Option<Entity<InputState>>,
+    /// Video the filename template preview is rendered for
+    preview_metadata: VideoMetadata,
+    /// Settings as last saved
+    settings: Settings,
+    /// Resolution picked in the settings panel, applied on save
+    selected_resolution: String,
+    /// Collision policy picked in the settings panel, applied on save
+    selected_collision_policy: CollisionPolicy,
+    settings_errors: SettingsErrors,
+    settings_saved: bool,
     show_settings: bool,
     focus_handle: FocusHandle,
     _subscriptions: Vec<Subscription>,
@@ -36,16 +80,112 @@ pub struct App {
 impl App {
     /// Create a new app instance
     pub fn new(cx: &mut Context<Self>) -> Self {
+        let settings = Settings::load().unwrap_or_else(|e| {
+            log::warn!("Using default settings: {}", e);
+            Settings::default()
+        });
+        let app_state = AppState::with_settings(settings.clone());
+
+        let startup_state = app_state.clone();
+        tokio::spawn(async move {
+            // Pick up where the last session left off
+            startup_state.start_pending_downloads().await;
+            if let Err(e) = crate::api::spawn(startup_state.clone()).await {
+                log::error!("Failed to start control API: {}", e);
+            }
+            startup_state.check_for_updates_on_startup().await
+        });
+
+        Self::watch_downloads(app_state.clone(), cx);
+
         Self {
-            app_state: AppState::new(),
+            app_state,
+            downloads: HashMap::new(),
+            groups: HashMap::new(),
+            add_error: None,
+            listing_playlist: false,
+            playlist_choice: None,
             url_input_state: None,
+            playlist_items_state: None,
             download_path_state: None,
+            max_concurrent_state: None,
+            output_template_state: None,
+            preview_metadata: template::sample_metadata(),
+            selected_resolution: settings.default_resolution.clone(),
+            selected_collision_policy: settings.collision_policy,
+            settings,
+            settings_errors: SettingsErrors::default(),
+            settings_saved: false,
             show_settings: false,
             focus_handle: cx.focus_handle(),
             _subscriptions: Vec::new(),
         }
     }
 
+    /// Keep `downloads` and `groups` in sync with the manager's event stream
+    fn watch_downloads(app_state: AppState, cx: &mut Context<Self>) {
+        cx.spawn(async move |this, cx| {
+            let mut events = app_state.subscribe().await;
+            let snapshot = app_state.get_downloads().await;
+            let groups = app_state.get_groups().await;
+            let mut known_groups: HashSet<Uuid> = groups.iter().map(|g| g.group.id).collect();
+            let seeded = this.update(cx, |app, cx| {
+                app.downloads = snapshot.into_iter().map(|d| (d.id, d)).collect();
+                app.set_groups(groups);
+                cx.notify();
+            });
+            if seeded.is_err() {
+                return;
+            }
+
+            loop {
+                let updated = match events.recv().await {
+                    Ok(event) => {
+                        // Items of a new playlist arrive before the view knows their group
+                        let groups = match &event {
+                            DownloadEvent::Queued { item, .. }
+                                if item.group_id.is_some_and(|id| !known_groups.contains(&id)) =>
+                            {
+                                let groups = app_state.get_groups().await;
+                                known_groups.extend(groups.iter().map(|g| g.group.id));
+                                Some(groups)
+                            }
+                            _ => None,
+                        };
+                        this.update(cx, |app, cx| {
+                            if let Some(groups) = groups {
+                                app.set_groups(groups);
+                            }
+                            event.apply(&mut app.downloads);
+                            cx.notify();
+                        })
+                    }
+                    Err(RecvError::Lagged(_)) => {
+                        let snapshot = app_state.get_downloads().await;
+                        let groups = app_state.get_groups().await;
+                        known_groups.extend(groups.iter().map(|g| g.group.id));
+                        this.update(cx, |app, cx| {
+                            app.downloads = snapshot.into_iter().map(|d| (d.id, d)).collect();
+                            app.set_groups(groups);
+                            cx.notify();
+                        })
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                // The view was dropped
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Replace the known groups with a fresh list from the manager
+    fn set_groups(&mut self, groups: Vec<GroupSummary>) {
+        self.groups = groups.into_iter().map(|g| (g.group.id, g.group)).collect();
+    }
+
     /// Initialize the input states with window access
     pub fn init_input_states(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         if self.url_input_state.is_none() {
@@ -56,13 +196,52 @@ impl App {
             }));
         }
 
+        if self.playlist_items_state.is_none() {
+            let playlist_items_state = cx.new(|cx| {
+                InputState::new(window, cx).placeholder("All, or e.g. 1-5,8,10-12")
+            });
+            // Re-render on every edit to keep the selection current
+            self._subscriptions.push(cx.subscribe_in(
+                &playlist_items_state,
+                window,
+                |_, _, _: &InputEvent, _, cx| cx.notify(),
+            ));
+            self.playlist_items_state = Some(playlist_items_state);
+        }
+
         if self.download_path_state.is_none() {
+            let download_path = self.settings.download_path.display().to_string();
             self.download_path_state = Some(cx.new(|cx| {
                 InputState::new(window, cx)
                     .placeholder("Download path...")
-                    .default_value("~/Downloads/ytdl-mini")
+                    .default_value(download_path)
             }));
         }
+
+        if self.max_concurrent_state.is_none() {
+            let max_concurrent = self.settings.max_concurrent_downloads.to_string();
+            self.max_concurrent_state = Some(cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder("3")
+                    .default_value(max_concurrent)
+            }));
+        }
+
+        if self.output_template_state.is_none() {
+            let output_template = self.settings.output_template.to_string();
+            let output_template_state = cx.new(|cx| {
+                InputState::new(window, cx)
+                    .placeholder(template::DEFAULT_TEMPLATE)
+                    .default_value(output_template)
+            });
+            // Re-render on every edit to keep the preview current
+            self._subscriptions.push(cx.subscribe_in(
+                &output_template_state,
+                window,
+                |_, _, _: &InputEvent, _, cx| cx.notify(),
+            ));
+            self.output_template_state = Some(output_template_state);
+        }
     }
 
     /// Handle adding a download
@@ -72,33 +251,270 @@ impl App {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if let Some(url_input_state) = &self.url_input_state {
-            let url = url_input_state.read(cx).value();
-            if !url.trim().is_empty() {
-                if crate::utils::is_valid_youtube_url(&url) {
-                    // For now, just clear the input and show a message
-                    log::info!("Adding download for: {}", url);
-                    // Clear the input
-                    url_input_state.update(cx, |state, cx| {
-                        state.set_value("", window, cx);
-                    });
-                } else {
-                    log::warn!("Invalid YouTube URL: {}", url);
-                }
+        let Some(url_input_state) = &self.url_input_state else {
+            return;
+        };
+        let url = url_input_state.read(cx).value().trim().to_string();
+        if url.is_empty() {
+            return;
+        }
+        if !crate::utils::is_valid_youtube_url(&url) {
+            self.add_error = Some(format!("Not a YouTube URL: {}", url));
+            cx.notify();
+            return;
+        }
+
+        log::info!("Adding download for: {}", url);
+        url_input_state.update(cx, |state, cx| {
+            state.set_value("", window, cx);
+        });
+        self.add_error = None;
+        cx.notify();
+
+        // Playlist expansion runs yt-dlp, which needs the tokio runtime
+        let app_state = self.app_state.clone();
+        if crate::utils::is_collection_url(&url) {
+            self.listing_playlist = true;
+            self.playlist_choice = None;
+            let task = tokio::spawn(async move {
+                let playlist = app_state.expand_playlist(&url).await?;
+                Ok::<_, YtdlError>(PlaylistChoice { url, playlist })
+            });
+            cx.spawn(async move |this, cx| {
+                let result = match task.await {
+                    Ok(Ok(choice)) if choice.playlist.entries.is_empty() => {
+                        Err(format!("No videos found in {}", choice.url))
+                    }
+                    Ok(Ok(choice)) => Ok(choice),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                let _ = this.update(cx, |app, cx| {
+                    app.listing_playlist = false;
+                    match result {
+                        Ok(choice) => app.playlist_choice = Some(choice),
+                        Err(error) => {
+                            log::error!("Failed to list playlist: {}", error);
+                            app.add_error = Some(error);
+                        }
+                    }
+                    cx.notify();
+                });
+            })
+            .detach();
+            return;
+        }
+
+        let task = tokio::spawn(async move { app_state.add_download(url).await });
+        cx.spawn(async move |this, cx| {
+            let error = match task.await {
+                Ok(Ok(_)) => return,
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => e.to_string(),
+            };
+            log::error!("Failed to add download: {}", error);
+            let _ = this.update(cx, |app, cx| {
+                app.add_error = Some(error);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Queue the entries picked from the expanded playlist
+    fn handle_queue_playlist(
+        &mut self,
+        _event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (Some(choice), Some(playlist_items_state)) =
+            (&self.playlist_choice, &self.playlist_items_state)
+        else {
+            return;
+        };
+        let selection = match self.playlist_selection(cx) {
+            Ok(selection) if choice.playlist.select(&selection).is_empty() => {
+                self.add_error = Some("No playlist entries selected".to_string());
+                cx.notify();
+                return;
             }
+            Ok(selection) => selection,
+            Err(error) => {
+                self.add_error = Some(error);
+                cx.notify();
+                return;
+            }
+        };
+
+        playlist_items_state.update(cx, |state, cx| {
+            state.set_value("", window, cx);
+        });
+        let Some(PlaylistChoice { url, playlist }) = self.playlist_choice.take() else {
+            return;
+        };
+        self.add_error = None;
+        cx.notify();
+
+        // Queueing may start downloads, which needs the tokio runtime
+        let app_state = self.app_state.clone();
+        let task = tokio::spawn(async move {
+            app_state.add_playlist(url, &playlist, &selection, None).await
+        });
+        cx.spawn(async move |this, cx| {
+            let error = match task.await {
+                Ok(Ok(_)) => return,
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => e.to_string(),
+            };
+            log::error!("Failed to queue playlist: {}", error);
+            let _ = this.update(cx, |app, cx| {
+                app.add_error = Some(error);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Close the playlist picker without queueing anything
+    fn handle_cancel_playlist(
+        &mut self,
+        _event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(playlist_items_state) = &self.playlist_items_state {
+            playlist_items_state.update(cx, |state, cx| {
+                state.set_value("", window, cx);
+            });
         }
+        self.playlist_choice = None;
+        self.add_error = None;
+        cx.notify();
+    }
+
+    /// The entries typed into the playlist picker
+    fn playlist_selection(&self, cx: &GpuiApp) -> Result<PlaylistSelection, String> {
+        let Some(playlist_items_state) = &self.playlist_items_state else {
+            return Ok(PlaylistSelection::All);
+        };
+        PlaylistSelection::parse(&playlist_items_state.read(cx).value()).map_err(setting_error)
     }
 
     /// Handle browse button click for download path
+    ///
+    /// The chosen folder is checked and saved as the download path right away.
     fn handle_browse_download_path(
         &mut self,
         _: &ClickEvent,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let picker = NativeFolderPicker::new(cx.to_async());
+        let app_state = self.app_state.clone();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let mut settings = app_state.get_settings().await;
+            let result = async {
+                let folder = choose_download_folder(&picker, &mut settings).await?;
+                if folder.is_some() {
+                    settings.save()?;
+                    // Applying settings may start queued downloads, which needs the tokio runtime
+                    let app_state = app_state.clone();
+                    let _ = tokio::spawn(async move { app_state.update_settings(settings).await })
+                        .await;
+                }
+                Ok::<_, YtdlError>(folder)
+            }
+            .await;
+            let _ = this.update_in(cx, |app, window, cx| {
+                match result {
+                    Ok(Some(folder)) => {
+                        if let Some(download_path_state) = &app.download_path_state {
+                            download_path_state.update(cx, |state, cx| {
+                                state.set_value(folder.display().to_string(), window, cx);
+                            });
+                        }
+                        app.settings.download_path = folder;
+                        app.settings_errors.download_path = None;
+                    }
+                    Ok(None) => {}
+                    Err(e) => app.settings_errors.download_path = Some(setting_error(e)),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// The values currently entered in the settings panel
+    fn settings_form(&self, cx: &GpuiApp) -> SettingsForm {
+        let value = |state: &Option<Entity<InputState>>| {
+            state.as_ref().map(|state| state.read(cx).value().trim().to_string())
+        };
+        SettingsForm {
+            resolution: self.selected_resolution.clone(),
+            collision_policy: self.selected_collision_policy,
+            download_path: value(&self.download_path_state),
+            max_concurrent: value(&self.max_concurrent_state),
+            output_template: value(&self.output_template_state),
+        }
+    }
+
+    /// Validate the settings panel and save it to config.json
+    ///
+    /// The panel's fields are applied to the current settings, so changes
+    /// made elsewhere, e.g. through the API, are kept. Nothing is saved unless
+    /// every field is valid; errors are shown next to their field.
+    fn handle_save_settings(
+        &mut self,
+        _event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) {
-        // This would open a file picker dialog
-        // For now, just a placeholder
-        log::info!("Browse for download path");
+        let form = self.settings_form(cx);
+        self.settings_saved = false;
+        cx.notify();
+
+        // Applying a new concurrency limit may start downloads, which needs the tokio runtime
+        let app_state = self.app_state.clone();
+        let task = tokio::spawn(async move {
+            let mut settings = app_state.get_settings().await;
+            let mut errors = form.apply(&mut settings);
+            if errors.is_empty() {
+                match settings.save() {
+                    Ok(()) => app_state.update_settings(settings.clone()).await,
+                    Err(e) => errors.save = Some(e.to_string()),
+                }
+            }
+            (settings, errors)
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let (settings, errors) = match task.await {
+                Ok(result) => result,
+                Err(e) => {
+                    log::error!("Failed to save settings: {}", e);
+                    return;
+                }
+            };
+            let _ = this.update_in(cx, |app, window, cx| {
+                if errors.is_empty() {
+                    // Show the path with `~` and variables expanded
+                    if let Some(download_path_state) = &app.download_path_state {
+                        let download_path = settings.download_path.display().to_string();
+                        download_path_state.update(cx, |state, cx| {
+                            state.set_value(download_path, window, cx);
+                        });
+                    }
+                    app.settings = settings;
+                    app.settings_saved = true;
+                }
+                app.settings_errors = errors;
+                cx.notify();
+            });
+        })
+        .detach();
     }
 
     /// Handle toggling settings
@@ -114,14 +530,10 @@ impl App {
 
     /// Render the URL input section
     fn render_url_input(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        div()
+        let input_row = div()
             .flex()
             .flex_row()
             .gap_3()
-            .p_4()
-            .bg(rgb(0x2d2d2d))
-            .border_b_1()
-            .border_color(rgb(0x404040))
             .child(if let Some(url_input_state) = &self.url_input_state {
                 TextInput::new(url_input_state)
                     .cleanable()
@@ -141,11 +553,150 @@ impl App {
                     .ghost()
                     .on_click(cx.listener(Self::handle_toggle_settings))
                     .child("Settings"),
-            )
+            );
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .p_4()
+            .bg(rgb(0x2d2d2d))
+            .border_b_1()
+            .border_color(rgb(0x404040))
+            .child(input_row)
+            .children(self.listing_playlist.then(|| {
+                div().text_sm().text_color(rgb(0x888888)).child("Listing playlist entries...")
+            }))
+            .children(self.add_error.clone().map(|error| {
+                div().text_sm().text_color(rgb(0xef9a9a)).child(error)
+            }))
+    }
+
+    /// Render the picker for which entries of an expanded playlist to queue
+    fn render_playlist_choice(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let choice = self.playlist_choice.as_ref()?;
+        let playlist_items_state = self.playlist_items_state.as_ref()?;
+
+        let selection = self.playlist_selection(cx);
+        let summary = match &selection {
+            Ok(selection) => div().text_sm().text_color(rgb(0x888888)).child(format!(
+                "{} of {} videos selected",
+                choice.playlist.select(selection).len(),
+                choice.playlist.entries.len()
+            )),
+            Err(error) => error_text(error.clone()),
+        };
+        let entries = choice.playlist.entries.iter().map(|entry| {
+            let selected = selection.as_ref().is_ok_and(|s| s.contains(entry.index));
+            components::playlist_entry_row(entry, selected)
+        });
+
+        Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap_2()
+                .p_4()
+                .bg(rgb(0x252525))
+                .border_b_1()
+                .border_color(rgb(0x404040))
+                .child(
+                    div()
+                        .text_color(rgb(0xffffff))
+                        .font_weight(gpui::FontWeight::BOLD)
+                        .child(choice.playlist.title.clone().unwrap_or_else(|| choice.url.clone())),
+                )
+                .child(
+                    div()
+                        .id("playlist-entries")
+                        .flex()
+                        .flex_col()
+                        .max_h(px(200.0))
+                        .overflow_y_scroll()
+                        .children(entries),
+                )
+                .child(setting_label("Videos to Queue:"))
+                .child(
+                    div()
+                        .flex()
+                        .flex_row()
+                        .gap_3()
+                        .child(TextInput::new(playlist_items_state).flex_1())
+                        .child(
+                            Button::new("queue-playlist")
+                                .primary()
+                                .on_click(cx.listener(Self::handle_queue_playlist))
+                                .child("Queue"),
+                        )
+                        .child(
+                            Button::new("cancel-playlist")
+                                .ghost()
+                                .on_click(cx.listener(Self::handle_cancel_playlist))
+                                .child("Cancel"),
+                        ),
+                )
+                .child(summary),
+        )
     }
 
     /// Render the downloads table
+    ///
+    /// Items of a playlist are listed together under a row with the playlist's progress.
     fn render_downloads_table(&self) -> impl IntoElement {
+        let mut downloads: Vec<&DownloadItem> = self.downloads.values().collect();
+        downloads.sort_by_key(|download| download.created_at);
+
+        let mut group_items: HashMap<Uuid, Vec<&DownloadItem>> = HashMap::new();
+        for &download in &downloads {
+            if let Some(group_id) = download.group_id {
+                group_items.entry(group_id).or_default().push(download);
+            }
+        }
+        let mut rows = Vec::new();
+        for &download in &downloads {
+            let Some(group_id) = download.group_id else {
+                rows.push(components::download_row(download).into_any_element());
+                continue;
+            };
+            // The whole group is listed at its first item
+            let Some(items) = group_items.remove(&group_id) else {
+                continue;
+            };
+            if let Some(group) = self.groups.get(&group_id) {
+                let progress = GroupProgress::of(items.iter().copied());
+                rows.push(components::group_row(group, &progress).into_any_element());
+            }
+            rows.extend(items.into_iter().map(|item| components::download_row(item).into_any_element()));
+        }
+
+        let content = if downloads.is_empty() {
+            // Empty state
+            div()
+                .flex()
+                .flex_col()
+                .items_center()
+                .justify_center()
+                .p_8()
+                .text_color(rgb(0x888888))
+                .child(div().text_xl().mb_2().child("📥"))
+                .child(div().mb_1().child("No downloads yet"))
+                .child(
+                    div()
+                        .text_sm()
+                        .child("Add a YouTube URL above to start downloading"),
+                )
+                .into_any_element()
+        } else {
+            div()
+                .id("downloads")
+                .flex()
+                .flex_col()
+                .flex_1()
+                .overflow_y_scroll()
+                .children(rows)
+                .into_any_element()
+        };
+
         div()
             .flex()
             .flex_col()
@@ -160,36 +711,61 @@ impl App {
                 div()
                     .flex()
                     .flex_row()
+                    .gap_2()
                     .p_3()
                     .bg(rgb(0x2d2d2d))
                     .border_b_1()
                     .border_color(rgb(0x404040))
                     .text_color(rgb(0xffffff))
                     .font_weight(gpui::FontWeight::BOLD)
-                    .child(div().flex_1().child("Status"))
-                    .child(div().flex().w(px(300.0)).child("Title"))
-                    .child(div().flex().w(px(200.0)).child("Created")),
-            )
-            .child(
-                // Table content
-                div().flex().flex_col().child(
-                    // Empty state
-                    div()
-                        .flex()
-                        .flex_col()
-                        .items_center()
-                        .justify_center()
-                        .p_8()
-                        .text_color(rgb(0x888888))
-                        .child(div().text_xl().mb_2().child("📥"))
-                        .child(div().mb_1().child("No downloads yet"))
-                        .child(
-                            div()
-                                .text_sm()
-                                .child("Add a YouTube URL above to start downloading"),
-                        ),
-                ),
+                    .child(div().w(px(components::THUMBNAIL_WIDTH)))
+                    .child(div().w(px(components::STATUS_WIDTH)).child("Status"))
+                    .child(div().flex_1().child("Title"))
+                    .child(div().w(px(components::PROGRESS_WIDTH)).child("Progress"))
+                    .child(div().w(px(components::SIZE_WIDTH)).child("Size"))
+                    .child(div().w(px(components::CREATED_WIDTH)).child("Created")),
             )
+            .child(content)
+    }
+
+    /// Render the filename template field with its presets and a live preview
+    fn render_output_template(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let Some(output_template_state) = &self.output_template_state else {
+            return div();
+        };
+
+        let presets = template::PRESETS.iter().map(|&(name, preset)| {
+            let output_template_state = output_template_state.clone();
+            Button::new(name)
+                .ghost()
+                .xsmall()
+                .on_click(move |_, window, cx| {
+                    output_template_state.update(cx, |state, cx| {
+                        state.set_value(preset, window, cx);
+                    });
+                })
+                .child(name)
+        });
+
+        // Validate as the user types; saving checks again
+        let text = output_template_state.read(cx).value();
+        let preview = match text.parse::<OutputTemplate>() {
+            Ok(output_template) => div()
+                .text_sm()
+                .text_color(rgb(0x888888))
+                .child(format!("Preview: {}", output_template.render(&self.preview_metadata))),
+            Err(e) => error_text(setting_error(e)),
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_2()
+            .child(setting_label("Filename Template:"))
+            .child(TextInput::new(output_template_state))
+            .child(div().flex().flex_row().flex_wrap().gap_1().children(presets))
+            // An invalid template's error already shows in place of the preview
+            .child(preview)
     }
 
     /// Render the settings panel
@@ -198,6 +774,41 @@ impl App {
             return div();
         }
 
+        let resolutions = RESOLUTIONS.iter().map(|&(value, label)| {
+            let selected = self.selected_resolution == value;
+            div()
+                .id(value)
+                .p_2()
+                .bg(rgb(if selected { 0x2d2d2d } else { 0x1e1e1e }))
+                .border_1()
+                .border_color(rgb(if selected { 0x0066cc } else { 0x404040 }))
+                .rounded_lg()
+                .text_color(rgb(if selected { 0xffffff } else { 0xcccccc }))
+                .hover(|style| style.bg(rgb(0x2d2d2d)))
+                .on_click(cx.listener(move |app, _: &ClickEvent, _, cx| {
+                    app.selected_resolution = value.to_string();
+                    app.settings_saved = false;
+                    cx.notify();
+                }))
+                .child(label)
+        });
+
+        let policies = CollisionPolicy::ALL.into_iter().map(|policy| {
+            let button = Button::new(policy.label())
+                .xsmall()
+                .on_click(cx.listener(move |app, _: &ClickEvent, _, cx| {
+                    app.selected_collision_policy = policy;
+                    app.settings_saved = false;
+                    cx.notify();
+                }))
+                .child(policy.label());
+            if self.selected_collision_policy == policy {
+                button.primary()
+            } else {
+                button.ghost()
+            }
+        });
+
         div()
             .absolute()
             .top(px(0.0))
@@ -226,73 +837,16 @@ impl App {
                             .flex()
                             .flex_col()
                             .gap_2()
-                            .child(
-                                div()
-                                    .text_color(rgb(0xcccccc))
-                                    .text_sm()
-                                    .child("Default Resolution:"),
-                            )
-                            .child(
-                                div()
-                                    .flex()
-                                    .flex_col()
-                                    .gap_1()
-                                    .child(
-                                        div()
-                                            .p_2()
-                                            .bg(rgb(0x2d2d2d))
-                                            .border_1()
-                                            .border_color(rgb(0x0066cc))
-                                            .rounded_lg()
-                                            .text_color(rgb(0xffffff))
-                                            .child("1920x1080 (1080p)"),
-                                    )
-                                    .child(
-                                        div()
-                                            .p_2()
-                                            .bg(rgb(0x1e1e1e))
-                                            .border_1()
-                                            .border_color(rgb(0x404040))
-                                            .rounded_lg()
-                                            .text_color(rgb(0xcccccc))
-                                            .hover(|style| style.bg(rgb(0x2d2d2d)))
-                                            .child("1280x720 (720p)"),
-                                    )
-                                    .child(
-                                        div()
-                                            .p_2()
-                                            .bg(rgb(0x1e1e1e))
-                                            .border_1()
-                                            .border_color(rgb(0x404040))
-                                            .rounded_lg()
-                                            .text_color(rgb(0xcccccc))
-                                            .hover(|style| style.bg(rgb(0x2d2d2d)))
-                                            .child("2560x1440 (1440p)"),
-                                    )
-                                    .child(
-                                        div()
-                                            .p_2()
-                                            .bg(rgb(0x1e1e1e))
-                                            .border_1()
-                                            .border_color(rgb(0x404040))
-                                            .rounded_lg()
-                                            .text_color(rgb(0xcccccc))
-                                            .hover(|style| style.bg(rgb(0x2d2d2d)))
-                                            .child("3840x2160 (4K)"),
-                                    ),
-                            ),
+                            .child(setting_label("Default Resolution:"))
+                            .child(div().flex().flex_col().gap_1().children(resolutions))
+                            .children(self.settings_errors.resolution.clone().map(error_text)),
                     )
                     .child(
                         div()
                             .flex()
                             .flex_col()
                             .gap_2()
-                            .child(
-                                div()
-                                    .text_color(rgb(0xcccccc))
-                                    .text_sm()
-                                    .child("Download Path:"),
-                            )
+                            .child(setting_label("Download Path:"))
                             .child(
                                 if let Some(download_path_state) = &self.download_path_state {
                                     TextInput::new(download_path_state)
@@ -309,12 +863,137 @@ impl App {
                                 } else {
                                     div().into_any_element()
                                 },
-                            ),
-                    ),
+                            )
+                            .children(self.settings_errors.download_path.clone().map(error_text)),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap_2()
+                            .child(setting_label("Concurrent Downloads:"))
+                            .child(if let Some(max_concurrent_state) = &self.max_concurrent_state {
+                                TextInput::new(max_concurrent_state).into_any_element()
+                            } else {
+                                div().into_any_element()
+                            })
+                            .children(self.settings_errors.max_concurrent.clone().map(error_text)),
+                    )
+                    .child(self.render_output_template(cx))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap_2()
+                            .child(setting_label("If the File Exists:"))
+                            .child(div().flex().flex_row().flex_wrap().gap_1().children(policies)),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_row()
+                            .items_center()
+                            .gap_3()
+                            .child(
+                                Button::new("save-settings")
+                                    .primary()
+                                    .on_click(cx.listener(Self::handle_save_settings))
+                                    .child("Save"),
+                            )
+                            .children(self.settings_saved.then(|| {
+                                div().text_sm().text_color(rgb(0x81c784)).child("Saved")
+                            })),
+                    )
+                    .children(self.settings_errors.save.clone().map(error_text)),
             )
     }
 }
 
+/// The fields the settings panel edits, as entered
+///
+/// Inputs that haven't been created yet are `None` and leave their setting alone.
+struct SettingsForm {
+    resolution: String,
+    collision_policy: CollisionPolicy,
+    download_path: Option<String>,
+    max_concurrent: Option<String>,
+    output_template: Option<String>,
+}
+
+impl SettingsForm {
+    /// Apply the form to `settings`, returning what was rejected
+    fn apply(self, settings: &mut Settings) -> SettingsErrors {
+        settings.collision_policy = self.collision_policy;
+        let mut errors = SettingsErrors {
+            resolution: settings.set_resolution(self.resolution).err().map(setting_error),
+            ..Default::default()
+        };
+
+        if let Some(path) = self.download_path {
+            errors.download_path = if path.is_empty() {
+                Some("Choose a download folder".to_string())
+            } else {
+                settings.set_download_path(expand_path(&path)).err().map(setting_error)
+            };
+        }
+        if let Some(max) = self.max_concurrent {
+            errors.max_concurrent = settings
+                .set_value("max_concurrent_downloads", &max)
+                .err()
+                .map(setting_error);
+        }
+        if let Some(output_template) = self.output_template {
+            errors.output_template = settings
+                .set_output_template(&output_template)
+                .err()
+                .map(setting_error);
+        }
+        errors
+    }
+}
+
+/// Validation errors shown in the settings panel, one per field
+#[derive(Debug, Default)]
+struct SettingsErrors {
+    resolution: Option<String>,
+    download_path: Option<String>,
+    max_concurrent: Option<String>,
+    output_template: Option<String>,
+    /// Writing config.json failed
+    save: Option<String>,
+}
+
+impl SettingsErrors {
+    fn is_empty(&self) -> bool {
+        self.resolution.is_none()
+            && self.download_path.is_none()
+            && self.max_concurrent.is_none()
+            && self.output_template.is_none()
+    }
+}
+
+/// A playlist expanded from the URL input, before any of it is queued
+struct PlaylistChoice {
+    url: String,
+    playlist: Playlist,
+}
+
+/// Message for a rejected setting, without the error type's prefix
+fn setting_error(error: YtdlError) -> String {
+    match error {
+        YtdlError::InvalidSetting(message) => message,
+        error => error.to_string(),
+    }
+}
+
+fn setting_label(text: &'static str) -> impl IntoElement {
+    div().text_color(rgb(0xcccccc)).text_sm().child(text)
+}
+
+fn error_text(message: String) -> Div {
+    div().text_sm().text_color(rgb(0xef9a9a)).child(message)
+}
+
 impl Render for App {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         // Initialize input states if they haven't been initialized yet
@@ -330,6 +1009,7 @@ impl Render for App {
                 // Header with URL input
                 self.render_url_input(cx),
             )
+            .children(self.render_playlist_choice(cx))
             .child(
                 // Main content area
                 div().flex().flex_1().child(self.render_downloads_table()),
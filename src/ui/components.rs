@@ -1,8 +1,208 @@
 // UI components module
-// This module will contain reusable UI components for GPUI
+// Reusable pieces of the main window
 
-use gpui::{div, prelude::*, SharedString, Render, IntoElement};
-use crate::core::{DownloadItem, DownloadStatus};
+use chrono::Local;
+use gpui::{div, img, prelude::*, px, relative, rgb, Div, IntoElement, ObjectFit, SharedString};
+use std::path::PathBuf;
 
-// TODO: Implement GPUI-based components
-// For now, this is a placeholder module
+use crate::core::{
+    CollisionOutcome, DownloadGroup, DownloadItem, DownloadStatus, GroupProgress, PlaylistEntry,
+};
+use crate::utils::{format_bytes, format_duration};
+
+/// Width of the columns, shared by the header and rows
+pub const THUMBNAIL_WIDTH: f32 = 80.0;
+pub const STATUS_WIDTH: f32 = 110.0;
+pub const PROGRESS_WIDTH: f32 = 260.0;
+pub const SIZE_WIDTH: f32 = 90.0;
+pub const CREATED_WIDTH: f32 = 140.0;
+
+/// A row of the downloads table
+pub fn download_row(download: &DownloadItem) -> impl IntoElement {
+    let title: SharedString = download.title.clone().unwrap_or_else(|| download.url.clone()).into();
+
+    div()
+        .flex()
+        .flex_row()
+        .items_center()
+        .gap_2()
+        .p_3()
+        .border_b_1()
+        .border_color(rgb(0x2d2d2d))
+        .text_color(rgb(0xffffff))
+        .text_sm()
+        .child(div().w(px(THUMBNAIL_WIDTH)).child(thumbnail(download)))
+        .child(div().w(px(STATUS_WIDTH)).child(status_badge(download)))
+        .child(div().flex_1().overflow_hidden().truncate().child(title))
+        .child(div().w(px(PROGRESS_WIDTH)).child(progress_cell(download)))
+        .child(
+            div()
+                .w(px(SIZE_WIDTH))
+                .text_color(rgb(0xcccccc))
+                .child(file_size(download).map(format_bytes).unwrap_or_default()),
+        )
+        .child(
+            div()
+                .w(px(CREATED_WIDTH))
+                .text_color(rgb(0x888888))
+                .child(download.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()),
+        )
+}
+
+/// Header row of a playlist group, with the progress of all its items
+pub fn group_row(group: &DownloadGroup, progress: &GroupProgress) -> impl IntoElement {
+    let title: SharedString = group.title.clone().unwrap_or_else(|| group.url.clone()).into();
+    let mut details = format!("{} of {} done", progress.completed, progress.total);
+    if progress.failed > 0 {
+        details.push_str(&format!(", {} failed", progress.failed));
+    }
+    if progress.active > 0 {
+        details.push_str(&format!(", {} downloading", progress.active));
+    }
+
+    div()
+        .flex()
+        .flex_row()
+        .items_center()
+        .gap_2()
+        .p_3()
+        .bg(rgb(0x252525))
+        .border_b_1()
+        .border_color(rgb(0x2d2d2d))
+        .text_color(rgb(0xffffff))
+        .text_sm()
+        .child(div().w(px(THUMBNAIL_WIDTH)))
+        .child(div().w(px(STATUS_WIDTH)).text_xs().text_color(rgb(0xaaaaaa)).child("Playlist"))
+        .child(
+            div()
+                .flex_1()
+                .overflow_hidden()
+                .truncate()
+                .font_weight(gpui::FontWeight::BOLD)
+                .child(title),
+        )
+        .child(div().w(px(PROGRESS_WIDTH)).child(progress_bar(progress.progress, details)))
+        .child(div().w(px(SIZE_WIDTH)))
+        .child(
+            div()
+                .w(px(CREATED_WIDTH))
+                .text_color(rgb(0x888888))
+                .child(group.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()),
+        )
+}
+
+/// A line of the playlist picker, dimmed when the entry is not selected
+pub fn playlist_entry_row(entry: &PlaylistEntry, selected: bool) -> impl IntoElement {
+    let title: SharedString = entry.title.clone().unwrap_or_else(|| entry.url.clone()).into();
+
+    div()
+        .flex()
+        .flex_row()
+        .gap_2()
+        .py_0p5()
+        .text_sm()
+        .text_color(rgb(if selected { 0xffffff } else { 0x666666 }))
+        .child(div().w(px(40.0)).text_color(rgb(0x888888)).child(entry.index.to_string()))
+        .child(div().flex_1().overflow_hidden().truncate().child(title))
+        .child(
+            div()
+                .text_color(rgb(0x888888))
+                .child(entry.duration.map(|d| format_duration(d as u64)).unwrap_or_default()),
+        )
+}
+
+/// The cached thumbnail, decoded by GPUI, with a placeholder until it is available
+fn thumbnail(download: &DownloadItem) -> impl IntoElement {
+    let placeholder = || div().size_full().rounded_md().bg(rgb(0x2d2d2d));
+    let frame = div().w(px(THUMBNAIL_WIDTH)).h(px(45.0)).rounded_md().overflow_hidden();
+
+    match &download.thumbnail_path {
+        Some(path) => frame.child(
+            img(PathBuf::from(path))
+                .size_full()
+                .object_fit(ObjectFit::Cover)
+                .with_loading(move || placeholder().into_any_element())
+                .with_fallback(move || placeholder().into_any_element()),
+        ),
+        None => frame.child(placeholder()),
+    }
+}
+
+/// Coloured label for a download's status
+fn status_badge(download: &DownloadItem) -> impl IntoElement {
+    let (label, color) = match &download.status {
+        DownloadStatus::Pending => ("Queued", 0x666666),
+        DownloadStatus::Downloading => ("Downloading", 0x0066cc),
+        DownloadStatus::Success if download.collision == Some(CollisionOutcome::Skipped) => {
+            ("Skipped", 0x546e7a)
+        }
+        DownloadStatus::Success => ("Done", 0x2e7d32),
+        DownloadStatus::Failed(_) => ("Failed", 0xc62828),
+        DownloadStatus::Cancelled => ("Cancelled", 0x555555),
+        DownloadStatus::Paused => ("Paused", 0xb26a00),
+    };
+
+    div()
+        .px_2()
+        .py_0p5()
+        .rounded_md()
+        .bg(rgb(color))
+        .text_xs()
+        .text_color(rgb(0xffffff))
+        .child(label)
+}
+
+/// Progress bar with speed and ETA while downloading, or the error of a failed download
+fn progress_cell(download: &DownloadItem) -> impl IntoElement {
+    if let DownloadStatus::Failed(error) = &download.status {
+        return div()
+            .text_xs()
+            .text_color(rgb(0xef9a9a))
+            .truncate()
+            .child(SharedString::from(error.clone()));
+    }
+
+    let progress = download.progress.clamp(0.0, 1.0);
+    let mut details = match download.collision {
+        Some(collision) => collision.description().to_string(),
+        None => format!("{:.1}%", progress * 100.0),
+    };
+    if download.status == DownloadStatus::Downloading {
+        if let Some(progress_details) = &download.progress_details {
+            if let Some(speed) = progress_details.speed {
+                details.push_str(&format!("  {}/s", format_bytes(speed as u64)));
+            }
+            if let Some(eta) = progress_details.eta {
+                details.push_str(&format!("  ETA {}", format_duration(eta)));
+            }
+        }
+    }
+
+    progress_bar(progress, details)
+}
+
+/// A bar filled to `progress` (0.0 to 1.0) with a line of details below
+fn progress_bar(progress: f32, details: String) -> Div {
+    let progress = progress.clamp(0.0, 1.0);
+
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .child(
+            div()
+                .h(px(6.0))
+                .w_full()
+                .rounded_md()
+                .bg(rgb(0x404040))
+                .child(div().h_full().w(relative(progress)).rounded_md().bg(rgb(0x0066cc))),
+        )
+        .child(div().text_xs().text_color(rgb(0xaaaaaa)).child(details))
+}
+
+/// Size of the download: the final file once finished, the expected total while running
+fn file_size(download: &DownloadItem) -> Option<u64> {
+    download
+        .file_size
+        .or_else(|| download.progress_details.as_ref().and_then(|details| details.total_bytes))
+}
@@ -0,0 +1,37 @@
+use gpui::{AsyncApp, PathPromptOptions};
+use std::path::PathBuf;
+
+use crate::core::{FolderPicker, YtdlError};
+
+/// The platform's native folder chooser, shown through GPUI
+pub struct NativeFolderPicker {
+    cx: AsyncApp,
+}
+
+impl NativeFolderPicker {
+    pub fn new(cx: AsyncApp) -> Self {
+        Self { cx }
+    }
+}
+
+impl FolderPicker for NativeFolderPicker {
+    async fn pick_folder(&self) -> Result<Option<PathBuf>, YtdlError> {
+        let options = PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some("Choose".into()),
+        };
+        let paths = self
+            .cx
+            .update(|cx| cx.prompt_for_paths(options))
+            .map_err(|_| YtdlError::InvalidState("The app is shutting down"))?;
+
+        match paths.await {
+            Ok(Ok(paths)) => Ok(paths.and_then(|paths| paths.into_iter().next())),
+            Ok(Err(e)) => Err(YtdlError::Io(std::io::Error::other(e.to_string()))),
+            // The dialog was dropped without an answer
+            Err(_) => Ok(None),
+        }
+    }
+}
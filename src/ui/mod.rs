@@ -1,4 +1,5 @@
 pub mod app;
 pub mod components;
+pub mod folder_picker;
 
 pub use app::{App, Backspace, SubmitUrl};
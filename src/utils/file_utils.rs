@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Get the default downloads directory for the current platform
 pub fn get_downloads_dir() -> PathBuf {
@@ -14,14 +14,131 @@ pub fn get_downloads_dir() -> PathBuf {
     }
 }
 
+/// Expand a leading `~` and `$VAR`/`${VAR}` environment variables in a path typed by the user
+///
+/// Variables that aren't set are left as written.
+pub fn expand_path(input: &str) -> PathBuf {
+    let input = input.trim();
+    let (mut expanded, rest) = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => (home.to_string_lossy().into_owned(), rest),
+            None => (String::new(), input),
+        },
+        _ => (String::new(), input),
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let braced = chars.next_if_eq(&'{').is_some();
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            name.push(c);
+        }
+        let closed = !braced || chars.next_if_eq(&'}').is_some();
+
+        match std::env::var(&name) {
+            Ok(value) if !name.is_empty() && closed => expanded.push_str(&value),
+            _ => {
+                expanded.push('$');
+                if braced {
+                    expanded.push('{');
+                }
+                expanded.push_str(&name);
+                if braced && closed {
+                    expanded.push('}');
+                }
+            }
+        }
+    }
+
+    PathBuf::from(expanded)
+}
+
+/// Check that files can be created in a directory by writing and removing a probe file
+pub fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".ytdl-mini-write-test-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+/// Lowercase hex SHA-256 of everything `reader` yields
+///
+/// Reads in chunks, so a large video file isn't loaded into memory.
+pub fn sha256_hex(mut reader: impl std::io::Read) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
 /// Ensure a directory exists, creating it if necessary
-pub fn ensure_dir_exists(path: &PathBuf) -> Result<(), anyhow::Error> {
+pub fn ensure_dir_exists(path: &PathBuf) -> std::io::Result<()> {
     if !path.exists() {
         std::fs::create_dir_all(path)?;
     }
     Ok(())
 }
 
+/// Write a file atomically by writing a temporary sibling and renaming it into place
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Remove the `.part`/`.ytdl` leftovers yt-dlp writes next to a destination file
+///
+/// Returns the number of files removed.
+pub fn remove_partial_files(destination: &Path) -> usize {
+    let (Some(dir), Some(name)) = (destination.parent(), destination.file_name()) else {
+        return 0;
+    };
+    let name = name.to_string_lossy();
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(suffix) = file_name.strip_prefix(name.as_ref()) else {
+            continue;
+        };
+
+        if is_partial_suffix(suffix) && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Whether a suffix after the destination name marks a yt-dlp temporary file
+fn is_partial_suffix(suffix: &str) -> bool {
+    suffix == ".part" || suffix == ".ytdl" || suffix.starts_with(".part-Frag")
+}
+
 /// Generate a safe filename from a video title
 pub fn sanitize_filename(title: &str) -> String {
     // Remove or replace characters that are not safe for filenames
@@ -60,6 +177,30 @@ pub fn get_file_extension(format: &str) -> &str {
     }
 }
 
+/// A uniquely named folder for tests, removed when dropped even if the test panics
+#[cfg(test)]
+pub(crate) struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("ytdl-mini-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,6 +213,44 @@ mod tests {
         assert_eq!(sanitize_filename("   "), "video");
     }
 
+    #[test]
+    fn test_remove_partial_files() {
+        let temp = TempDir::new();
+        let dir = temp.path();
+        for name in ["video.mp4.part", "video.mp4.ytdl", "video.mp4.part-Frag3", "video.mp4", "other.mp4.part"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        assert_eq!(remove_partial_files(&dir.join("video.mp4")), 3);
+        assert!(dir.join("video.mp4").exists());
+        assert!(dir.join("other.mp4.part").exists());
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let home = dirs::home_dir().unwrap();
+        std::env::set_var("YTDL_MINI_TEST_DIR", "videos");
+
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/Downloads"), home.join("Downloads"));
+        assert_eq!(expand_path("/data/$YTDL_MINI_TEST_DIR/new"), PathBuf::from("/data/videos/new"));
+        assert_eq!(expand_path("/data/${YTDL_MINI_TEST_DIR}x"), PathBuf::from("/data/videosx"));
+        assert_eq!(expand_path("/data/$YTDL_MINI_UNSET_VAR"), PathBuf::from("/data/$YTDL_MINI_UNSET_VAR"));
+        assert_eq!(expand_path("~user/videos"), PathBuf::from("~user/videos"));
+        assert_eq!(expand_path("/price$"), PathBuf::from("/price$"));
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        let temp = TempDir::new();
+        let path = temp.path().join("video.mp4");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_hex(std::fs::File::open(&path).unwrap()).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn test_get_file_extension() {
         assert_eq!(get_file_extension("mp4"), "mp4");
@@ -1,5 +1,7 @@
 pub mod url_validator;
 pub mod file_utils;
+pub mod format_utils;
 
-pub use url_validator::is_valid_youtube_url;
-pub use file_utils::get_downloads_dir;
+pub use url_validator::{is_collection_url, is_valid_youtube_url, normalize_collection_url};
+pub use file_utils::{expand_path, get_downloads_dir};
+pub use format_utils::{format_bytes, format_duration};
@@ -19,6 +19,54 @@ pub fn is_valid_youtube_url(url_str: &str) -> bool {
     }
 }
 
+/// Check whether a YouTube URL points at a playlist, channel or mix rather than one video
+pub fn is_collection_url(url_str: &str) -> bool {
+    let Ok(url) = Url::parse(url_str) else {
+        return false;
+    };
+    if !is_valid_youtube_url(url_str) || url.host_str() == Some("youtu.be") {
+        return false;
+    }
+
+    let list = url.query_pairs()
+        .find(|(key, _)| key == "list")
+        .map(|(_, value)| value.to_string());
+
+    match url.path_segments().and_then(|mut segments| segments.next()) {
+        // Format: https://www.youtube.com/playlist?list=PLAYLIST_ID
+        Some("playlist") => list.is_some(),
+        // Mixes are generated playlists attached to a watch URL: list=RD...
+        Some("watch") => list.is_some_and(|list| list.starts_with("RD")),
+        // Format: https://www.youtube.com/@handle/videos, /channel/ID, /c/NAME, /user/NAME
+        Some("channel" | "c" | "user") => true,
+        Some(segment) => segment.starts_with('@'),
+        None => false,
+    }
+}
+
+/// Point bare channel URLs at their videos tab so they expand to videos, not tabs
+pub fn normalize_collection_url(url_str: &str) -> String {
+    let Ok(mut url) = Url::parse(url_str) else {
+        return url_str.to_string();
+    };
+
+    let segments: Vec<String> = url
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+
+    let is_bare_channel = match segments.as_slice() {
+        [handle] => handle.starts_with('@'),
+        [kind, _] => matches!(kind.as_str(), "channel" | "c" | "user"),
+        _ => false,
+    };
+
+    if is_bare_channel {
+        url.set_path(&format!("{}/videos", segments.join("/")));
+    }
+    url.to_string()
+}
+
 /// Extract video ID from YouTube URL
 pub fn extract_video_id(url_str: &str) -> Option<String> {
     let url = Url::parse(url_str).ok()?;
@@ -58,6 +106,33 @@ mod tests {
         assert!(!is_valid_youtube_url(""));
     }
 
+    #[test]
+    fn test_collection_urls() {
+        assert!(is_collection_url("https://www.youtube.com/playlist?list=PL590L5WQmH8fJ54F369BLDSqIwcs-TCfs"));
+        assert!(is_collection_url("https://www.youtube.com/@LexFridman/videos"));
+        assert!(is_collection_url("https://www.youtube.com/channel/UC_x5XG1OV2P6uZZ5FSM9Ttw"));
+        assert!(is_collection_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=RDdQw4w9WgXcQ"));
+        assert!(!is_collection_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"));
+        assert!(!is_collection_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL590L5WQmH8f"));
+        assert!(!is_collection_url("https://youtu.be/dQw4w9WgXcQ"));
+    }
+
+    #[test]
+    fn test_normalize_collection_url() {
+        assert_eq!(
+            normalize_collection_url("https://www.youtube.com/@LexFridman"),
+            "https://www.youtube.com/@LexFridman/videos"
+        );
+        assert_eq!(
+            normalize_collection_url("https://www.youtube.com/channel/UC_x5XG1OV2P6uZZ5FSM9Ttw"),
+            "https://www.youtube.com/channel/UC_x5XG1OV2P6uZZ5FSM9Ttw/videos"
+        );
+        assert_eq!(
+            normalize_collection_url("https://www.youtube.com/@LexFridman/streams"),
+            "https://www.youtube.com/@LexFridman/streams"
+        );
+    }
+
     #[test]
     fn test_extract_video_id() {
         assert_eq!(